@@ -8,19 +8,133 @@
 
 use anyhow::{Context, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Whisper 要求的采样率
+const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// 实际采集时长与墙钟时长相差超过此比例时视为时钟漂移 / 丢帧
+const CLOCK_DRIFT_WARN_RATIO: f64 = 0.05;
+
+/// 短于此时长（毫秒）的录音不做漂移判断：开流延迟等固定开销占比过大
+const CLOCK_DRIFT_MIN_MS: u64 = 3000;
+
+/// 静音裁剪的检测窗口（16kHz 下 20ms）
+const TRIM_WINDOW_SAMPLES: usize = 320;
+
+/// 静音裁剪后在语音前后保留的余量（16kHz 下 100ms），避免切掉字头字尾的弱音
+const TRIM_PADDING_SAMPLES: usize = 1600;
 
 /// 录音器结构体
 /// 封装了 cpal 的音频流，负责从麦克风采集 PCM 数据
 pub struct AudioRecorder {
-    // cpal 的音频流（启动后开始采集数据）
+    // cpal 的音频流（启动后开始采集数据；开启预录时停止录音后仍保持运行）
     stream: Option<cpal::Stream>,
-    // 原始录音数据缓冲区（原生采样率、原生声道数）
-    buffer: Arc<Mutex<Vec<f32>>>,
+    // 音频回调写入的共享数据（录音缓冲区、预录环形缓冲区、错误标记）
+    capture: CaptureTargets,
     /// 设备原生采样率（Hz），stop() 时用于重采样
     native_sample_rate: u32,
     /// 设备原生声道数，stop() 时用于混音到单声道
     native_channels: usize,
+    /// 当前（或最近一次）使用的输入设备名称
+    device_name: String,
+    /// 预录时长（毫秒，0 = 关闭）
+    pre_roll_ms: u64,
+    /// 指定的音频后端名称（None = 系统默认后端）
+    host_name: Option<String>,
+    /// 指定的输入设备名称（None = 系统默认设备）
+    input_device: Option<String>,
+    /// 首尾静音裁剪阈值（RMS，0 = 不裁剪）
+    trim_threshold: f32,
+    /// 噪声门阈值（单个样本的绝对值，0 = 关闭）
+    noise_gate_threshold: f32,
+    /// 本次录音开始采集的墙钟时间，stop() 时与实际采集样本数对比
+    capture_started: Option<Instant>,
+    /// 录音开头拼入的预录样本数（原生格式），不计入采集时长对比
+    pre_roll_samples: usize,
+    /// 最近一次录音的采集时长对比结果
+    last_timing: Option<CaptureTiming>,
+}
+
+/// 录音采集时长对比：按样本数计算的时长 vs 墙钟时长
+///
+/// 设备时钟漂移或回调丢帧时两者会明显偏离，导致按样本数推算的时间戳不准
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureTiming {
+    /// 墙钟时长（毫秒，从开始采集到停止）
+    pub expected_ms: u64,
+    /// 按实际采集样本数和原生采样率计算的时长（毫秒，不含预录）
+    pub captured_ms: u64,
+    /// captured_ms / expected_ms（1.0 = 完全一致）
+    pub ratio: f64,
+    /// 偏差超过阈值（录音足够长时才判断）
+    pub drifted: bool,
+}
+
+/// 音频回调与录音器共享的数据
+#[derive(Clone)]
+struct CaptureTargets {
+    // 原始录音数据缓冲区（原生采样率、原生声道数）
+    buffer: Arc<Mutex<Vec<f32>>>,
+    // 未录音时的预录环形缓冲区（原生格式），start() 时拼到录音开头
+    pre_roll: Arc<Mutex<VecDeque<f32>>>,
+    // 预录环形缓冲区容量（样本数，0 = 不预录）
+    pre_roll_capacity: Arc<AtomicUsize>,
+    // 是否正在录音：true 写入 buffer，false 写入预录缓冲区
+    capturing: Arc<AtomicBool>,
+    // 录音流错误（由 cpal 错误回调写入，如设备被拔出），开流时清空
+    stream_error: Arc<Mutex<Option<String>>>,
+    // 音量发送端：录音中每个回调块的 RMS 发往此处，供音量表使用
+    level_tx: Arc<Mutex<Option<LevelSender>>>,
+}
+
+/// 音量通道发送端（有界，满时丢弃，不阻塞音频回调）
+pub type LevelSender = tokio::sync::mpsc::Sender<f32>;
+
+/// 音量表平滑：指数移动平均，音量上升用 attack 系数、下降用 release 系数
+///
+/// 系数范围 0~1，越大跟随越快（1 = 不平滑）；
+/// 通常 attack 较大让音量表快速响应说话，release 较小让回落平缓不闪烁
+#[derive(Debug, Clone)]
+pub struct LevelMeter {
+    attack: f32,
+    release: f32,
+    level: f32,
+}
+
+impl LevelMeter {
+    pub fn new(attack: f32, release: f32) -> Self {
+        LevelMeter {
+            attack: attack.clamp(0.0, 1.0),
+            release: release.clamp(0.0, 1.0),
+            level: 0.0,
+        }
+    }
+
+    /// 输入新的音量值，返回平滑后的音量
+    pub fn update(&mut self, input: f32) -> f32 {
+        let coeff = if input > self.level { self.attack } else { self.release };
+        self.level += coeff * (input - self.level);
+        self.level
+    }
+}
+
+impl CaptureTargets {
+    fn new() -> Self {
+        CaptureTargets {
+            buffer: Arc::new(Mutex::new(Vec::new())),
+            pre_roll: Arc::new(Mutex::new(VecDeque::new())),
+            pre_roll_capacity: Arc::new(AtomicUsize::new(0)),
+            capturing: Arc::new(AtomicBool::new(false)),
+            stream_error: Arc::new(Mutex::new(None)),
+            level_tx: Arc::new(Mutex::new(None)),
+        }
+    }
 }
 
 impl AudioRecorder {
@@ -28,9 +142,18 @@ impl AudioRecorder {
     pub fn new() -> Self {
         AudioRecorder {
             stream: None,
-            buffer: Arc::new(Mutex::new(Vec::new())),
+            capture: CaptureTargets::new(),
             native_sample_rate: 44100, // 保守默认值，start() 会覆盖
             native_channels: 1,
+            device_name: String::new(),
+            pre_roll_ms: 0,
+            host_name: None,
+            input_device: None,
+            trim_threshold: 0.0,
+            noise_gate_threshold: 0.0,
+            capture_started: None,
+            pre_roll_samples: 0,
+            last_timing: None,
         }
     }
 
@@ -38,23 +161,129 @@ impl AudioRecorder {
     ///
     /// 使用设备的原生配置（采样率、声道数），不强制要求 16kHz，
     /// 避免设备不支持导致 build_input_stream 失败。
+    /// 开启预录时复用常驻的音频流，并把预录缓冲区中的音频放在录音开头。
     pub fn start(&mut self) -> Result<()> {
-        // 获取默认音频主机（macOS 上是 CoreAudio）
-        let host = cpal::default_host();
+        // 常驻流出过错（如设备已拔出）时重新开流
+        if self.stream.is_some() && self.stream_error().is_some() {
+            log::warn!("常驻录音流已失效，重新打开");
+            self.stream = None;
+        }
+
+        // 清空缓冲区，准备新的录音；有预录数据时放在开头
+        {
+            let mut buf = self.capture.buffer.lock().unwrap();
+            buf.clear();
+            let mut ring = self.capture.pre_roll.lock().unwrap();
+            buf.extend(ring.drain(..));
+        }
+
+        if self.stream.is_none() {
+            self.open_stream()?;
+        }
+        self.capture.capturing.store(true, Ordering::SeqCst);
+        self.capture_started = Some(Instant::now());
+        self.pre_roll_samples = self.buffer_len();
+        self.last_timing = None;
+
+        log::info!(
+            "录音已开始（{}Hz {}ch → 停止后重采样到 16kHz 单声道，预录 {} 样本）",
+            self.native_sample_rate, self.native_channels, self.pre_roll_samples
+        );
+        Ok(())
+    }
+
+    /// 设置预录时长（0 = 关闭）
+    ///
+    /// 开启后即使未在录音也保持音频流运行，只在内存中保留最近 ms 毫秒的音频，
+    /// 有少量常驻 CPU 开销，且系统会一直显示麦克风占用
+    pub fn set_pre_roll(&mut self, ms: u64) -> Result<()> {
+        self.pre_roll_ms = ms;
+        let recording = self.is_recording();
+
+        if ms == 0 {
+            self.capture.pre_roll_capacity.store(0, Ordering::SeqCst);
+            self.capture.pre_roll.lock().unwrap().clear();
+            if !recording && self.stream.take().is_some() {
+                log::info!("预录已关闭，常驻录音流已停止");
+            }
+            return Ok(());
+        }
+
+        if self.stream.is_none() {
+            self.open_stream()?;
+            log::info!("预录已开启（{} ms），常驻录音流已启动", ms);
+        } else {
+            self.update_pre_roll_capacity();
+        }
+        Ok(())
+    }
+
+    /// 指定音频后端（None = 系统默认），下次开流时生效
+    ///
+    /// 常驻的预录流会立即按新后端重新打开
+    pub fn set_host(&mut self, host_name: Option<String>) -> Result<()> {
+        if self.host_name == host_name {
+            return Ok(());
+        }
+        self.host_name = host_name;
+
+        if self.stream.is_some() && !self.is_recording() {
+            self.stream = None;
+            if self.pre_roll_ms > 0 {
+                self.open_stream()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 指定输入设备（None = 系统默认），下次开流时生效；找不到该设备时回退到默认设备
+    ///
+    /// 常驻的预录流会立即按新设备重新打开
+    pub fn set_input_device(&mut self, device_name: Option<String>) -> Result<()> {
+        let device_name = device_name.filter(|n| !n.is_empty());
+        if self.input_device == device_name {
+            return Ok(());
+        }
+        self.input_device = device_name;
+
+        if self.stream.is_some() && !self.is_recording() {
+            self.stream = None;
+            if self.pre_roll_ms > 0 {
+                self.open_stream()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 设置首尾静音裁剪阈值（RMS，0 = 不裁剪），stop() 时生效
+    pub fn set_trim_threshold(&mut self, threshold: f32) {
+        self.trim_threshold = threshold.max(0.0);
+    }
+
+    /// 设置噪声门阈值（0 = 关闭，限制在 0~1），stop() 时生效
+    pub fn set_noise_gate_threshold(&mut self, threshold: f32) {
+        self.noise_gate_threshold = threshold.clamp(0.0, 1.0);
+    }
+
+    /// 打开输入设备的音频流（不改变 capturing 标记）
+    fn open_stream(&mut self) -> Result<()> {
+        // 获取音频主机（macOS 上是 CoreAudio；Windows 可选 WASAPI / ASIO）
+        let host = select_host(self.host_name.as_deref());
         log::info!("使用音频主机: {:?}", host.id());
 
-        // 获取默认输入设备（麦克风）
-        let device = host
-            .default_input_device()
-            .context("未找到默认输入设备（麦克风）\n请检查：1.是否授权麦克风权限  2.是否插入麦克风")?;
+        // 获取输入设备（麦克风）：优先用户指定的设备，否则用默认设备；
+        // 没有默认设备时优先找回上次使用的设备
+        let preferred = Some(self.device_name.as_str()).filter(|n| !n.is_empty());
+        let device = select_input_device(&host, self.input_device.as_deref())
+            .or_else(|| find_input_device(&host, preferred))
+            .context("未找到输入设备（麦克风）\n请检查：1.是否授权麦克风权限  2.是否插入麦克风")?;
 
         let device_name = device.name().unwrap_or_else(|_| "未知设备".to_string());
         log::info!("使用麦克风: {}", device_name);
 
-        // 获取设备支持的默认配置（macOS 通常是 44100Hz / 48000Hz 双声道 f32）
-        let supported_config = device
-            .default_input_config()
-            .context("无法获取设备默认输入配置")?;
+        // 选择输入配置：设备支持 16kHz 单声道时直接使用（免重采样），
+        // 否则使用默认配置（macOS 通常是 44100Hz / 48000Hz 双声道 f32）
+        let supported_config = pick_input_config(&device)?;
 
         let native_sample_rate = supported_config.sample_rate().0;
         let native_channels   = supported_config.channels() as usize;
@@ -64,67 +293,88 @@ impl AudioRecorder {
         );
 
         // 将 SupportedStreamConfig → StreamConfig（保留原生参数）
+        let sample_format = supported_config.sample_format();
         let stream_config: cpal::StreamConfig = supported_config.into();
 
-        // 清空缓冲区，准备新的录音
-        {
-            let mut buf = self.buffer.lock().unwrap();
-            buf.clear();
-        }
+        // 新流的格式可能与旧的预录数据不同，清空重来
+        *self.capture.stream_error.lock().unwrap() = None;
+        self.capture.pre_roll.lock().unwrap().clear();
+        self.native_sample_rate = native_sample_rate;
+        self.native_channels    = native_channels;
+        self.update_pre_roll_capacity();
+
+        // 克隆共享数据引用，供音频回调闭包使用
+        let targets = self.capture.clone();
 
-        // 克隆缓冲区引用，供音频回调闭包使用
-        let buffer_clone = Arc::clone(&self.buffer);
-
-        // 构建输入流（cpal 负责从设备原生格式转换为 f32）
-        let stream = device
-            .build_input_stream(
-                &stream_config,
-                move |data: &[f32], _info: &cpal::InputCallbackInfo| {
-                    let mut buf = buffer_clone.lock().unwrap();
-                    buf.extend_from_slice(data);
-                },
-                |err| {
-                    log::error!("录音回调错误: {}", err);
-                },
-                None,
-            )
-            .context("无法创建音频输入流")?;
+        // 构建输入流：按设备原生采样格式选择回调类型，在回调中统一转换为 f32
+        // （部分 Windows 设备只提供 I16 / U16，直接按 f32 建流会失败）
+        let stream = match sample_format {
+            cpal::SampleFormat::F32 => build_input_stream::<f32>(&device, &stream_config, targets, |s| s)?,
+            cpal::SampleFormat::I16 => build_input_stream::<i16>(&device, &stream_config, targets, i16_to_f32)?,
+            cpal::SampleFormat::U16 => build_input_stream::<u16>(&device, &stream_config, targets, u16_to_f32)?,
+            other => anyhow::bail!("不支持的采样格式: {:?}", other),
+        };
 
         // 启动流
         stream.play().context("无法启动音频流")?;
 
-        // 保存流引用和设备参数
-        self.stream              = Some(stream);
-        self.native_sample_rate  = native_sample_rate;
-        self.native_channels     = native_channels;
-
-        log::info!(
-            "录音已开始（{}Hz {}ch → 停止后重采样到 16kHz 单声道）",
-            native_sample_rate, native_channels
-        );
+        // 保存流引用和设备名称
+        self.stream      = Some(stream);
+        self.device_name = device_name;
         Ok(())
     }
 
+    /// 按当前设备参数重新计算预录缓冲区容量
+    fn update_pre_roll_capacity(&self) {
+        let capacity = (self.native_sample_rate as u64 * self.pre_roll_ms / 1000) as usize
+            * self.native_channels.max(1);
+        self.capture.pre_roll_capacity.store(capacity, Ordering::SeqCst);
+        let mut ring = self.capture.pre_roll.lock().unwrap();
+        let excess = ring.len().saturating_sub(capacity);
+        ring.drain(..excess);
+    }
+
+    /// 设置（或清除）音量发送端，录音中每个回调块的 RMS 都会尝试发送
+    pub fn set_level_sender(&mut self, tx: Option<LevelSender>) {
+        *self.capture.level_tx.lock().unwrap() = tx;
+    }
+
     /// 停止录音，返回已重采样到 16000Hz 单声道的 PCM 数据
+    ///
+    /// 开启预录时音频流保持运行，继续填充预录缓冲区
     pub fn stop(&mut self) -> Vec<f32> {
+        self.stop_with_channels(false).0
+    }
+
+    /// 停止录音，返回单声道混音数据；preserve_channels 为 true 且设备为多声道时，
+    /// 额外返回每个声道各自重采样到 16kHz 的数据（用于按声道区分说话人）
+    pub fn stop_with_channels(&mut self, preserve_channels: bool) -> (Vec<f32>, Option<Vec<Vec<f32>>>) {
+        self.capture.capturing.store(false, Ordering::SeqCst);
+        // 清除音量发送端：接收端随之结束
+        self.set_level_sender(None);
+
         // 停止流（drop 触发 cpal 停止采集）
-        if let Some(stream) = self.stream.take() {
-            drop(stream);
-            log::info!("录音流已停止");
+        if self.pre_roll_ms == 0 {
+            if let Some(stream) = self.stream.take() {
+                drop(stream);
+                log::info!("录音流已停止");
+            }
         }
 
-        // 取出原始缓冲区数据
+        // 取出原始缓冲区数据；预录缓冲区从此刻重新积累
         let raw_data = {
-            let mut buf = self.buffer.lock().unwrap();
+            let mut buf = self.capture.buffer.lock().unwrap();
             let data = buf.clone();
             buf.clear();
             data
         };
+        self.capture.pre_roll.lock().unwrap().clear();
 
         log::info!(
             "原始数据: {} 样本（{}Hz {}ch）",
             raw_data.len(), self.native_sample_rate, self.native_channels
         );
+        self.last_timing = self.measure_timing(raw_data.len());
 
         // 重采样 + 混音 → 16kHz 单声道
         const TARGET_RATE: u32 = 16000;
@@ -135,6 +385,30 @@ impl AudioRecorder {
             TARGET_RATE,
         );
 
+        // 裁剪首尾静音（分声道数据按同一范围裁剪，保持对齐）
+        let (trim_start, trim_end) = silence_bounds(&resampled, self.trim_threshold);
+        let mut resampled = if trim_start > 0 || trim_end < resampled.len() {
+            log::info!(
+                "已裁剪首尾静音: 开头 {} 样本, 结尾 {} 样本",
+                trim_start, resampled.len() - trim_end
+            );
+            resampled[trim_start..trim_end].to_vec()
+        } else {
+            resampled
+        };
+
+        // 噪声门：混音后逐样本处理，低于阈值的样本置零
+        if self.noise_gate_threshold > 0.0 && !resampled.is_empty() {
+            let gated = noise_gate(&mut resampled, self.noise_gate_threshold);
+            log::info!(
+                "噪声门（阈值 {:.4}）: 已置零 {} / {} 样本（{:.1}%）",
+                self.noise_gate_threshold,
+                gated,
+                resampled.len(),
+                gated as f64 / resampled.len() as f64 * 100.0
+            );
+        }
+
         let duration_ms = (resampled.len() as f64 / TARGET_RATE as f64 * 1000.0) as u64;
 
         // 计算音频统计信息（帮助诊断 Windows 上录音问题）
@@ -152,30 +426,423 @@ impl AudioRecorder {
             );
         }
 
-        resampled
+        // 分声道：交错数据拆成各声道后分别重采样
+        let channels = if preserve_channels && self.native_channels >= 2 {
+            let n = self.native_channels;
+            let per_channel: Vec<Vec<f32>> = (0..n)
+                .map(|c| {
+                    let samples: Vec<f32> = raw_data.iter().skip(c).step_by(n).copied().collect();
+                    let channel = resample_to_mono(&samples, self.native_sample_rate, 1, TARGET_RATE);
+                    let end = trim_end.min(channel.len());
+                    let mut channel = channel[trim_start.min(end)..end].to_vec();
+                    if self.noise_gate_threshold > 0.0 {
+                        noise_gate(&mut channel, self.noise_gate_threshold);
+                    }
+                    channel
+                })
+                .collect();
+            log::info!("已保留 {} 个声道的独立音频", n);
+            Some(per_channel)
+        } else {
+            None
+        };
+
+        (resampled, channels)
+    }
+
+    /// 对比实际采集时长与墙钟时长，偏差过大时记录警告
+    fn measure_timing(&mut self, raw_len: usize) -> Option<CaptureTiming> {
+        let started = self.capture_started.take()?;
+        let expected_ms = started.elapsed().as_millis() as u64;
+        let frames = raw_len.saturating_sub(self.pre_roll_samples) / self.native_channels.max(1);
+        let captured_ms = (frames as f64 / self.native_sample_rate.max(1) as f64 * 1000.0) as u64;
+        if expected_ms == 0 {
+            return None;
+        }
+
+        let ratio = captured_ms as f64 / expected_ms as f64;
+        let drifted = expected_ms >= CLOCK_DRIFT_MIN_MS && (ratio - 1.0).abs() > CLOCK_DRIFT_WARN_RATIO;
+        if drifted {
+            log::warn!(
+                "采集时长与实际时长不符：采集 {}ms / 实际 {}ms（比例 {:.3}），可能是设备时钟漂移或回调丢帧",
+                captured_ms, expected_ms, ratio
+            );
+        } else {
+            log::debug!("采集时长 {}ms / 实际 {}ms（比例 {:.3}）", captured_ms, expected_ms, ratio);
+        }
+        Some(CaptureTiming { expected_ms, captured_ms, ratio, drifted })
+    }
+
+    /// 最近一次录音的采集时长对比（未录过音或录音未正常开始时为 None）
+    pub fn last_capture_timing(&self) -> Option<CaptureTiming> {
+        self.last_timing.clone()
     }
 
-    /// 检查当前是否正在录音
+    /// 检查当前是否正在录音（预录的常驻流不算录音）
     pub fn is_recording(&self) -> bool {
-        self.stream.is_some()
+        self.stream.is_some() && self.capture.capturing.load(Ordering::SeqCst)
+    }
+
+    /// 录音流错误（设备断开等）；None 表示录音流正常
+    pub fn stream_error(&self) -> Option<String> {
+        self.capture.stream_error.lock().unwrap().clone()
+    }
+
+    /// 当前（或最近一次）使用的输入设备名称
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    /// 设备原生采样率（Hz）
+    pub fn native_sample_rate(&self) -> u32 {
+        self.native_sample_rate
+    }
+
+    /// 设备原生声道数
+    pub fn native_channels(&self) -> usize {
+        self.native_channels
+    }
+
+    /// 计算最近 window_ms 毫秒原始音频的 RMS 音量（录音中实时查询）
+    pub fn peek_recent_rms(&self, window_ms: u64) -> f32 {
+        let window = (self.native_sample_rate as u64 * window_ms / 1000) as usize
+            * self.native_channels.max(1);
+        let buf = self.capture.buffer.lock().unwrap();
+        let start = buf.len().saturating_sub(window);
+        crate::whisper::audio_rms(&buf[start..])
     }
 
     /// 获取当前缓冲区中的样本数量（原生采样率）
     pub fn buffer_len(&self) -> usize {
-        self.buffer.lock().unwrap().len()
+        self.capture.buffer.lock().unwrap().len()
     }
 }
 
 // cpal::Stream 是线程安全的，显式标记以满足 Tauri 的 Send 要求
 unsafe impl Send for AudioRecorder {}
 
+/// 按指定采样类型构建输入流，回调中用 convert 把样本转换为 f32
+/// 录音中写入录音缓冲区并发送本块音量，未录音时写入预录环形缓冲区（超出容量丢弃最旧样本）
+/// 流出错时把错误信息写入 stream_error（只保留第一次错误）
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    targets: CaptureTargets,
+    convert: fn(T) -> f32,
+) -> Result<cpal::Stream>
+where
+    T: cpal::SizedSample + Send + 'static,
+{
+    let error_slot = Arc::clone(&targets.stream_error);
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _info: &cpal::InputCallbackInfo| {
+                if targets.capturing.load(Ordering::Relaxed) {
+                    let mut buf = targets.buffer.lock().unwrap();
+                    let start = buf.len();
+                    buf.extend(data.iter().map(|&s| convert(s)));
+                    if let Some(tx) = targets.level_tx.lock().unwrap().as_ref() {
+                        let _ = tx.try_send(crate::whisper::audio_rms(&buf[start..]));
+                    }
+                    return;
+                }
+
+                let capacity = targets.pre_roll_capacity.load(Ordering::Relaxed);
+                if capacity == 0 {
+                    return;
+                }
+                let mut ring = targets.pre_roll.lock().unwrap();
+                ring.extend(data.iter().map(|&s| convert(s)));
+                let excess = ring.len().saturating_sub(capacity);
+                ring.drain(..excess);
+            },
+            move |err| {
+                log::error!("录音回调错误: {}", err);
+                let message = match err {
+                    cpal::StreamError::DeviceNotAvailable => "录音设备已断开".to_string(),
+                    other => format!("录音设备错误: {}", other),
+                };
+                let mut slot = error_slot.lock().unwrap();
+                if slot.is_none() {
+                    *slot = Some(message);
+                }
+            },
+            None,
+        )
+        .context("无法创建音频输入流")
+}
+
+// ===== 音频后端 =====
+
+/// 可用的音频后端
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioHostInfo {
+    /// 后端名称（如 "WASAPI" / "ASIO" / "CoreAudio"），即 audio_host 设置的取值
+    pub name: String,
+    pub is_default: bool,
+}
+
+/// 列出当前平台可用的音频后端
+pub fn list_hosts() -> Vec<AudioHostInfo> {
+    let default_id = cpal::default_host().id();
+    cpal::available_hosts()
+        .into_iter()
+        .map(|id| AudioHostInfo {
+            name: id.name().to_string(),
+            is_default: id == default_id,
+        })
+        .collect()
+}
+
+/// 按名称选择音频后端；未指定、不存在或不可用时回退到默认后端
+pub fn select_host(name: Option<&str>) -> cpal::Host {
+    let Some(name) = name.filter(|n| !n.is_empty()) else {
+        return cpal::default_host();
+    };
+
+    let id = cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(name));
+    match id.map(cpal::host_from_id) {
+        Some(Ok(host)) => host,
+        Some(Err(e)) => {
+            log::warn!("音频后端 {} 不可用（{}），使用默认后端", name, e);
+            cpal::default_host()
+        }
+        None => {
+            log::warn!("未找到音频后端 {}，使用默认后端", name);
+            cpal::default_host()
+        }
+    }
+}
+
+/// 选择输入设备：优先系统默认设备
+///
+/// 部分 Windows / Linux 环境下 default_input_device() 返回 None 但实际有麦克风，
+/// 此时遍历输入设备：名称与 preferred 相同的优先，否则取第一个能查询到默认配置的设备
+pub fn find_input_device(host: &cpal::Host, preferred: Option<&str>) -> Option<cpal::Device> {
+    if let Some(device) = host.default_input_device() {
+        return Some(device);
+    }
+
+    let mut usable: Vec<cpal::Device> = host.input_devices()
+        .map(|devices| devices.filter(|d| d.default_input_config().is_ok()).collect())
+        .unwrap_or_default();
+    if usable.is_empty() {
+        return None;
+    }
+
+    let index = preferred
+        .and_then(|name| usable.iter().position(|d| d.name().is_ok_and(|n| n == name)))
+        .unwrap_or(0);
+    let device = usable.swap_remove(index);
+
+    log::warn!(
+        "系统没有默认输入设备，自动选择: {}",
+        device.name().unwrap_or_else(|_| "未知设备".to_string())
+    );
+    Some(device)
+}
+
+/// 按名称查找输入设备（None / 找不到时返回 None，由调用方回退到默认设备）
+pub fn select_input_device(host: &cpal::Host, name: Option<&str>) -> Option<cpal::Device> {
+    let name = name.filter(|n| !n.is_empty())?;
+    let device = host.input_devices()
+        .ok()?
+        .find(|d| d.name().is_ok_and(|n| n == name));
+    if device.is_none() {
+        log::warn!("未找到输入设备 {}，使用默认设备", name);
+    }
+    device
+}
+
+/// 输入设备信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputDeviceInfo {
+    /// 设备名称，即 input_device 设置的取值
+    pub name: String,
+    pub is_default: bool,
+}
+
+/// 列出指定音频后端（None = 系统默认）下的输入设备
+pub fn list_input_devices(host_name: Option<&str>) -> Result<Vec<InputDeviceInfo>> {
+    let host = select_host(host_name);
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+    let devices = host.input_devices()
+        .context("无法枚举输入设备")?
+        .filter_map(|d| d.name().ok())
+        .map(|name| InputDeviceInfo {
+            is_default: default_name.as_deref() == Some(name.as_str()),
+            name,
+        })
+        .collect();
+    Ok(devices)
+}
+
+// ===== 设备能力 =====
+
+/// 设备支持的一组输入配置（采样率为范围）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputConfigRange {
+    pub sample_format: String,
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+}
+
+/// 输入设备能力
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceCapabilities {
+    pub device_name: String,
+    /// 设备默认配置（格式, 声道数, 采样率）
+    pub default_format: String,
+    pub default_channels: u16,
+    pub default_sample_rate: u32,
+    /// 设备声明支持的全部配置
+    pub configs: Vec<InputConfigRange>,
+    /// 是否可直接以 16kHz 单声道（f32 / i16）采集（免重采样）
+    pub supports_16k_mono: bool,
+}
+
+/// 查询输入设备支持的配置
+/// host_name: 音频后端名称（None = 系统默认）；device_name: 输入设备名称（None / 找不到 = 默认设备）
+pub fn probe_input_device(host_name: Option<&str>, device_name: Option<&str>) -> Result<DeviceCapabilities> {
+    let host = select_host(host_name);
+    let device = select_input_device(&host, device_name)
+        .or_else(|| find_input_device(&host, None))
+        .context("未找到输入设备（麦克风）")?;
+    let device_name = device.name().unwrap_or_else(|_| "未知设备".to_string());
+
+    let configs: Vec<InputConfigRange> = device
+        .supported_input_configs()
+        .context("无法查询设备支持的输入配置")?
+        .map(|c| InputConfigRange {
+            sample_format: format!("{:?}", c.sample_format()),
+            channels: c.channels(),
+            min_sample_rate: c.min_sample_rate().0,
+            max_sample_rate: c.max_sample_rate().0,
+        })
+        .collect();
+
+    if configs.is_empty() {
+        anyhow::bail!("设备 {} 未声明任何可用的输入配置", device_name);
+    }
+
+    let default_config = device
+        .default_input_config()
+        .context("无法获取设备默认输入配置")?;
+
+    Ok(DeviceCapabilities {
+        device_name,
+        default_format: format!("{:?}", default_config.sample_format()),
+        default_channels: default_config.channels(),
+        default_sample_rate: default_config.sample_rate().0,
+        supports_16k_mono: find_16k_mono_config(&device).is_some(),
+        configs,
+    })
+}
+
+/// 在设备支持的配置中查找 16kHz 单声道 f32 / i16（优先 f32，i16 在回调中转换）
+fn find_16k_mono_config(device: &cpal::Device) -> Option<cpal::SupportedStreamConfig> {
+    let target = cpal::SampleRate(WHISPER_SAMPLE_RATE);
+    let candidates: Vec<cpal::SupportedStreamConfigRange> = device
+        .supported_input_configs()
+        .ok()?
+        .filter(|c| {
+            c.channels() == 1
+                && c.min_sample_rate() <= target
+                && c.max_sample_rate() >= target
+        })
+        .collect();
+
+    [cpal::SampleFormat::F32, cpal::SampleFormat::I16]
+        .into_iter()
+        .find_map(|format| candidates.iter().find(|c| c.sample_format() == format))
+        .map(|c| c.clone().with_sample_rate(target))
+}
+
+/// 选择录音配置：优先 16kHz 单声道（Whisper 友好，stop() 时免重采样），否则用设备默认配置
+fn pick_input_config(device: &cpal::Device) -> Result<cpal::SupportedStreamConfig> {
+    if let Some(config) = find_16k_mono_config(device) {
+        log::info!("设备支持 16kHz 单声道，直接以该配置采集");
+        return Ok(config);
+    }
+    device
+        .default_input_config()
+        .context("无法获取设备默认输入配置")
+}
+
+// ===== 静音裁剪 =====
+
+/// 裁剪首尾 RMS 低于 threshold 的窗口（16kHz 单声道），语音前后各保留少量余量
+///
+/// threshold 为 0 或整段都低于阈值时原样返回（交给后续的音量检查处理）。
+/// 录音结束时需要同一范围裁剪分声道数据，stop_with_channels 直接使用 silence_bounds
+pub fn trim_silence(samples: &[f32], threshold: f32) -> &[f32] {
+    let (start, end) = silence_bounds(samples, threshold);
+    &samples[start..end]
+}
+
+/// 计算裁剪首尾静音后保留的范围 [start, end)（16kHz 单声道）
+///
+/// 首尾 RMS 低于 threshold 的窗口被裁掉，语音前后各保留少量余量；
+/// threshold 为 0 或整段都低于阈值时返回完整范围（交给后续的音量检查处理）。
+/// 只返回范围，录音结束时混音数据和分声道数据按同一范围裁剪
+fn silence_bounds(samples: &[f32], threshold: f32) -> (usize, usize) {
+    if threshold <= 0.0 || samples.is_empty() {
+        return (0, samples.len());
+    }
+
+    let is_voiced = |chunk: &[f32]| crate::whisper::audio_rms(chunk) >= threshold;
+    let windows: Vec<&[f32]> = samples.chunks(TRIM_WINDOW_SAMPLES).collect();
+    let Some(first) = windows.iter().position(|w| is_voiced(w)) else {
+        return (0, samples.len());
+    };
+    let last = windows.iter().rposition(|w| is_voiced(w)).unwrap_or(first);
+
+    let start = (first * TRIM_WINDOW_SAMPLES).saturating_sub(TRIM_PADDING_SAMPLES);
+    let end = ((last + 1) * TRIM_WINDOW_SAMPLES + TRIM_PADDING_SAMPLES).min(samples.len());
+    (start, end)
+}
+
+// ===== 噪声门 =====
+
+/// 硬噪声门：绝对值低于 threshold 的样本置零（无 attack / release），返回置零的样本数
+///
+/// 用于压低底噪、电流声等持续的低电平噪声；threshold 过高会切掉弱辅音
+pub fn noise_gate(samples: &mut [f32], threshold: f32) -> usize {
+    if threshold <= 0.0 {
+        return 0;
+    }
+    let mut gated = 0;
+    for sample in samples.iter_mut() {
+        if sample.abs() < threshold {
+            *sample = 0.0;
+            gated += 1;
+        }
+    }
+    gated
+}
+
 // ===== 重采样工具 =====
 
-/// 多声道原生采样 → 单声道目标采样率（线性插值）
+/// 抗混叠低通滤波器的抽头数（奇数，越多过渡带越窄，计算量越大）
+const ANTI_ALIAS_TAPS: usize = 63;
+
+/// 低通截止频率相对目标奈奎斯特频率的比例（留出过渡带）
+const ANTI_ALIAS_CUTOFF: f64 = 0.9;
+
+/// 多声道原生采样 → 单声道目标采样率
 ///
 /// 两步操作：
 /// 1. 按帧混音：多声道取平均 → 单声道
-/// 2. 线性插值：从 native_rate 降采样到 target_rate
+/// 2. 重采样：降采样时先经过窗函数 sinc 低通（截止在目标奈奎斯特频率以下），
+///    再线性插值；直接线性插值会把高于 8kHz 的成分混叠到语音频段，影响辅音识别
 fn resample_to_mono(
     data: &[f32],
     native_rate: u32,
@@ -197,28 +864,78 @@ fn resample_to_mono(
             .collect()
     };
 
-    // 第二步：线性插值重采样
+    // 第二步：重采样（采样率相同直接返回）
     if native_rate == target_rate {
         return mono;
     }
 
+    // 降采样才需要抗混叠；升采样（如 8kHz 设备）直接插值
+    let taps = (native_rate > target_rate)
+        .then(|| lowpass_taps(ANTI_ALIAS_CUTOFF * 0.5 * target_rate as f64 / native_rate as f64));
+    let sample_at = |idx: usize| -> f32 {
+        match &taps {
+            Some(taps) => filtered_sample(&mono, taps, idx),
+            None => mono.get(idx).copied().unwrap_or(0.0),
+        }
+    };
+
     let ratio   = native_rate as f64 / target_rate as f64;
     let out_len = ((mono.len() as f64) / ratio).ceil() as usize;
     let mut resampled = Vec::with_capacity(out_len);
 
+    // 只在插值用到的位置计算滤波结果，不对整段原始数据做卷积
     for i in 0..out_len {
         let src_pos = i as f64 * ratio;
         let idx     = src_pos as usize;
         let frac    = (src_pos - idx as f64) as f32;
 
-        let s0 = mono.get(idx).copied().unwrap_or(0.0);
-        let s1 = mono.get(idx + 1).copied().unwrap_or(s0);
+        let s0 = sample_at(idx);
+        let s1 = if idx + 1 < mono.len() { sample_at(idx + 1) } else { s0 };
         resampled.push(s0 + (s1 - s0) * frac);
     }
 
     resampled
 }
 
+/// Blackman 窗 sinc 低通滤波器系数（cutoff 为归一化截止频率，单位：周期 / 样本，0 ~ 0.5）
+/// 系数归一化为直流增益 1
+fn lowpass_taps(cutoff: f64) -> Vec<f32> {
+    use std::f64::consts::PI;
+
+    let half = (ANTI_ALIAS_TAPS / 2) as f64;
+    let mut taps: Vec<f64> = (0..ANTI_ALIAS_TAPS)
+        .map(|n| {
+            let x = n as f64 - half;
+            let sinc = if x == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * PI * cutoff * x).sin() / (PI * x)
+            };
+            let phase = 2.0 * PI * n as f64 / (ANTI_ALIAS_TAPS - 1) as f64;
+            let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+            sinc * window
+        })
+        .collect();
+
+    let sum: f64 = taps.iter().sum();
+    if sum != 0.0 {
+        taps.iter_mut().for_each(|t| *t /= sum);
+    }
+    taps.into_iter().map(|t| t as f32).collect()
+}
+
+/// 在 idx 处计算低通滤波后的样本（以 idx 为中心卷积，超出范围的样本视为 0）
+fn filtered_sample(samples: &[f32], taps: &[f32], idx: usize) -> f32 {
+    let half = taps.len() / 2;
+    taps.iter()
+        .enumerate()
+        .filter_map(|(k, &t)| {
+            let pos = (idx + k).checked_sub(half)?;
+            samples.get(pos).map(|&s| s * t)
+        })
+        .sum()
+}
+
 // ===== 工具函数（供其他模块使用）=====
 
 /// 将 PCM f32 数据转换为 i16 格式（WAV 标准格式）
@@ -232,7 +949,110 @@ pub fn f32_to_i16(samples: &[f32]) -> Vec<i16> {
         .collect()
 }
 
+/// i16 样本 → f32（-1.0 ~ 1.0）
+pub fn i16_to_f32(sample: i16) -> f32 {
+    sample as f32 / 32768.0
+}
+
+/// u16 样本（以 32768 为零点）→ f32（-1.0 ~ 1.0）
+pub fn u16_to_f32(sample: u16) -> f32 {
+    (sample as f32 - 32768.0) / 32768.0
+}
+
 /// 计算录音时长（毫秒）
 pub fn samples_to_ms(sample_count: usize, sample_rate: u32) -> u64 {
     (sample_count as f64 / sample_rate as f64 * 1000.0) as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f32 = 1e-6;
+
+    #[test]
+    fn level_meter_rises_by_attack() {
+        let mut meter = LevelMeter::new(0.6, 0.15);
+        // 从 0 阶跃到 1：每次更新移动剩余差值的 attack 比例
+        let first = meter.update(1.0);
+        assert!((first - 0.6).abs() < EPS);
+        let second = meter.update(1.0);
+        assert!((second - (0.6 + 0.6 * 0.4)).abs() < EPS);
+    }
+
+    #[test]
+    fn level_meter_falls_by_release() {
+        let mut meter = LevelMeter::new(1.0, 0.15);
+        assert!((meter.update(1.0) - 1.0).abs() < EPS);
+        // 从 1 阶跃到 0：每次更新移动剩余差值的 release 比例
+        let first = meter.update(0.0);
+        assert!((first - 0.85).abs() < EPS);
+        let second = meter.update(0.0);
+        assert!((second - 0.85 * 0.85).abs() < EPS);
+    }
+
+    /// 生成 1 秒正弦波（幅度 0.5，RMS ≈ 0.354）
+    fn sine(freq: f64, rate: u32) -> Vec<f32> {
+        (0..rate)
+            .map(|n| (0.5 * (2.0 * std::f64::consts::PI * freq * n as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    /// 去掉首尾各 100ms（滤波器边缘效应）后的 RMS
+    fn middle_rms(samples: &[f32]) -> f32 {
+        let edge = 1600;
+        crate::whisper::audio_rms(&samples[edge..samples.len() - edge])
+    }
+
+    #[test]
+    fn resample_attenuates_tone_above_new_nyquist() {
+        // 12kHz 高于 16kHz 的奈奎斯特频率（8kHz），不滤波会混叠成 4kHz
+        let out = resample_to_mono(&sine(12_000.0, 48_000), 48_000, 1, 16_000);
+        assert_eq!(out.len(), 16_000);
+        let rms = middle_rms(&out);
+        assert!(rms < 0.01, "12kHz 应被滤除，实际 RMS={}", rms);
+    }
+
+    #[test]
+    fn resample_passes_tone_below_new_nyquist() {
+        let out = resample_to_mono(&sine(1_000.0, 48_000), 48_000, 1, 16_000);
+        let rms = middle_rms(&out);
+        let expected = 0.5 / std::f32::consts::SQRT_2;
+        assert!((rms - expected).abs() < expected * 0.05, "1kHz 应保留，实际 RMS={}", rms);
+    }
+
+    /// 前后各 1 秒静音，中间 0.2 秒恒定音量的"语音"
+    fn padded_speech() -> Vec<f32> {
+        let mut samples = vec![0.0; 16000];
+        samples.resize(16000 + 3200, 0.5);
+        samples.resize(16000 + 3200 + 16000, 0.0);
+        samples
+    }
+
+    #[test]
+    fn trim_silence_is_noop_when_disabled() {
+        let samples = padded_speech();
+        assert_eq!(silence_bounds(&samples, 0.0), (0, samples.len()));
+        assert_eq!(trim_silence(&samples, 0.0).len(), samples.len());
+    }
+
+    #[test]
+    fn trim_silence_keeps_all_silent_input() {
+        let samples = vec![0.001; 8000];
+        assert_eq!(silence_bounds(&samples, 0.01), (0, samples.len()));
+        assert_eq!(trim_silence(&samples, 0.01).len(), samples.len());
+    }
+
+    #[test]
+    fn trim_silence_cuts_leading_and_trailing_silence_with_padding() {
+        let samples = padded_speech();
+        let (start, end) = silence_bounds(&samples, 0.01);
+        assert_eq!(start, 16000 - TRIM_PADDING_SAMPLES);
+        assert_eq!(end, 16000 + 3200 + TRIM_PADDING_SAMPLES);
+
+        let trimmed = trim_silence(&samples, 0.01);
+        assert_eq!(trimmed.len(), 3200 + 2 * TRIM_PADDING_SAMPLES);
+        assert!(trimmed[..TRIM_PADDING_SAMPLES].iter().all(|&s| s == 0.0));
+        assert!(trimmed[TRIM_PADDING_SAMPLES..TRIM_PADDING_SAMPLES + 3200].iter().all(|&s| s == 0.5));
+    }
+}
@@ -1,19 +1,51 @@
 // cloud/mod.rs - 云端 ASR API 调用
 //
-// 支持两种协议：
-// 1. OpenAI 兼容（multipart/form-data）：OpenAI / 火山引擎 / 讯飞 / 自定义
+// 支持四种协议：
+// 1. OpenAI 兼容（multipart/form-data）：OpenAI / 火山引擎 / 讯飞 / Groq / 自定义
 // 2. 阿里云 NLS RESTful API（裸字节 POST）：阿里云一句话识别
+// 3. Deepgram 预录音频接口（裸字节 POST，嵌套 JSON 响应）
+// 4. Azure 语音服务短音频 REST 接口（按区域的 URL，裸 WAV POST）
 
 use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
 use reqwest::multipart;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::RwLock;
 use std::time::Duration;
 use crate::state::CloudProvider;
 
+/// 云端识别的音频输入
+pub enum CloudAudio {
+    /// 16 kHz 单声道 f32 PCM 数据（实时录音），上传前编码为 WAV
+    Pcm(Vec<f32>),
+    /// 已编码的音频文件（如 mp3 / m4a），原样上传，避免解码再编码的损失
+    Encoded {
+        bytes: Vec<u8>,
+        /// MIME 类型（如 "audio/mpeg"）
+        mime: String,
+        /// 上传时的文件名，OpenAI 兼容接口按扩展名判断格式（如 "audio.mp3"）
+        file_name: String,
+    },
+}
+
+impl CloudAudio {
+    /// 转为上传用的 (字节, MIME, 文件名)
+    fn into_upload(self) -> (Vec<u8>, String, String) {
+        match self {
+            CloudAudio::Pcm(samples) => (
+                encode_wav(&samples, 16000, 1),
+                "audio/wav".to_string(),
+                "audio.wav".to_string(),
+            ),
+            CloudAudio::Encoded { bytes, mime, file_name } => (bytes, mime, file_name),
+        }
+    }
+}
+
 /// 云端识别入参
 pub struct CloudTranscribeParams {
-    /// 16 kHz 单声道 f32 PCM 数据
-    pub audio_samples: Vec<f32>,
+    pub audio: CloudAudio,
     /// "zh" / "en" / "auto"
     pub language: String,
     pub provider: CloudProvider,
@@ -23,6 +55,14 @@ pub struct CloudTranscribeParams {
     /// OpenAI 兼容：Bearer Token（sk-...）
     /// 阿里云 NLS：X-NLS-Token（来自控制台总览页）
     pub api_key: String,
+    /// 模型名覆盖（None / 空 = 使用服务商默认模型）
+    pub model: Option<String>,
+    /// OpenAI 兼容：response_format（"json" / "text"）
+    pub response_format: String,
+    /// 上传音频大小上限（字节，0 = 不限制）
+    pub max_upload_bytes: u64,
+    /// 临时性失败（网络错误 / 429 / 5xx）的最大重试次数
+    pub max_retries: u32,
 }
 
 // ===== OpenAI 兼容响应 =====
@@ -31,6 +71,44 @@ struct OpenAITranscriptionResponse {
     text: String,
 }
 
+// ===== Deepgram 响应 =====
+// {"results":{"channels":[{"alternatives":[{"transcript":"...","confidence":0.98}]}]}}
+#[derive(Debug, Deserialize)]
+struct DeepgramResponse {
+    results: DeepgramResults,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepgramResults {
+    #[serde(default)]
+    channels: Vec<DeepgramChannel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepgramChannel {
+    #[serde(default)]
+    alternatives: Vec<DeepgramAlternative>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepgramAlternative {
+    #[serde(default)]
+    transcript: String,
+}
+
+// ===== Azure 短音频响应 =====
+// {"RecognitionStatus":"Success","DisplayText":"北京的天气。","Offset":0,"Duration":12300000}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AzureResponse {
+    recognition_status: String,
+    #[serde(default)]
+    display_text: String,
+}
+
+/// Azure 短音频 REST 接口的最长音频时长（秒）
+const AZURE_SHORT_AUDIO_MAX_SECS: usize = 60;
+
 // ===== 阿里云 NLS 响应 =====
 #[derive(Debug, Deserialize)]
 struct NlsResponse {
@@ -39,6 +117,15 @@ struct NlsResponse {
     message: Option<String>,
 }
 
+/// 阿里云 NLS 一句话识别的最长音频时长（秒）
+const ALIYUN_NLS_MAX_SECS: usize = 60;
+
+/// 重试退避的初始等待（毫秒），之后每次翻倍
+const RETRY_BASE_DELAY_MS: u64 = 1000;
+
+/// 单次重试的最长等待（秒），服务商 Retry-After 更长时也按此截断
+const RETRY_MAX_DELAY_SECS: u64 = 30;
+
 // ===== WAV 编码 =====
 
 /// 把 f32 PCM 编码为 WAV 字节（16-bit PCM，单声道，16 kHz）
@@ -84,78 +171,404 @@ pub fn encode_wav(samples: &[f32], sample_rate: u32, channels: u16) -> Vec<u8> {
     wav
 }
 
+/// WAV 文件头中的格式信息
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WavInfo {
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u16,
+    /// 按 data 块大小和字节率计算的时长（秒）
+    duration_secs: f64,
+}
+
+/// 解析 WAV 文件头（逐个遍历 RIFF 子块，兼容 fmt 与 data 之间夹有 LIST 等其他块的文件）
+fn parse_wav_header(bytes: &[u8]) -> Result<WavInfo> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        anyhow::bail!("不是有效的 WAV 文件");
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+    let mut fmt: Option<(u16, u32, u32, u16)> = None; // 声道数、采样率、字节率、位深
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32_at(pos + 4) as usize;
+        let body = pos + 8;
+        if id == b"fmt " {
+            if size < 16 || body + 16 > bytes.len() {
+                anyhow::bail!("WAV 文件的 fmt 块不完整");
+            }
+            fmt = Some((u16_at(body + 2), u32_at(body + 4), u32_at(body + 8), u16_at(body + 14)));
+        } else if id == b"data" {
+            let (channels, sample_rate, byte_rate, bits_per_sample) =
+                fmt.context("WAV 文件缺少 fmt 块")?;
+            if byte_rate == 0 {
+                anyhow::bail!("WAV 文件的字节率为 0");
+            }
+            // 流式写出的 WAV 可能把 data 大小写成 0 / 0xFFFFFFFF，按实际剩余字节计算
+            let data_len = size.min(bytes.len() - body);
+            let data_len = if data_len == 0 { bytes.len() - body } else { data_len };
+            return Ok(WavInfo {
+                sample_rate,
+                channels,
+                bits_per_sample,
+                duration_secs: data_len as f64 / byte_rate as f64,
+            });
+        }
+        // 子块按偶数字节对齐
+        pos = body.saturating_add(size).saturating_add(size & 1);
+    }
+    anyhow::bail!("WAV 文件缺少 data 块")
+}
+
+/// 校验上传的 WAV 文件并返回格式信息：时长不超过 max_secs、采样率在 sample_rates 之内
+fn check_wav_upload(bytes: &[u8], max_secs: usize, sample_rates: &[u32], provider: &str) -> Result<WavInfo> {
+    let info = parse_wav_header(bytes)
+        .with_context(|| format!("{}：无法解析上传的 WAV 文件", provider))?;
+    if info.duration_secs > max_secs as f64 {
+        anyhow::bail!(
+            "录音过长，超出云端大小限制：{}最长 {} 秒（当前 {:.1} 秒），请缩短录音",
+            provider, max_secs, info.duration_secs
+        );
+    }
+    if !sample_rates.contains(&info.sample_rate) {
+        anyhow::bail!(
+            "{}不支持 {} Hz 的 WAV（支持 {:?} Hz），请转换采样率或改用本地识别",
+            provider, info.sample_rate, sample_rates
+        );
+    }
+    if info.channels != 1 || info.bits_per_sample != 16 {
+        anyhow::bail!(
+            "{}只支持 16-bit 单声道 WAV（当前 {}-bit {} 声道）",
+            provider, info.bits_per_sample, info.channels
+        );
+    }
+    Ok(info)
+}
+
 // ===== 主入口 =====
 
 /// 执行云端语音识别，根据 provider 分发到对应实现
+/// 临时性失败按 params.max_retries 指数退避重试
 pub async fn transcribe_cloud(params: CloudTranscribeParams) -> Result<String> {
     match &params.provider {
         // 阿里云走专属 NLS RESTful 接口（裸 WAV POST）
-        CloudProvider::Aliyun => transcribe_aliyun_nls(&params).await,
+        CloudProvider::Aliyun => {
+            with_retries(params.max_retries, || transcribe_aliyun_nls(&params)).await
+        }
+        // Deepgram 走原生接口（裸音频 POST）
+        CloudProvider::Deepgram => transcribe_deepgram(params).await,
+        // Azure 走短音频 REST 接口（按区域的 URL）
+        CloudProvider::Azure => {
+            with_retries(params.max_retries, || transcribe_azure(&params)).await
+        }
         // 其余服务商走 OpenAI 兼容接口（multipart/form-data）
         _ => transcribe_openai_compatible(params).await,
     }
 }
 
+// ===== 重试 =====
+
+/// 服务端错误响应（非 2xx，429 单独用 RateLimitedError 表示）
+#[derive(Debug, Clone)]
+pub struct ApiStatusError {
+    pub status: u16,
+    pub body: String,
+}
+
+impl std::fmt::Display for ApiStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "API 错误 {}: {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for ApiStatusError {}
+
+/// 是否为值得重试的临时性失败：网络错误 / 超时、429、500 / 502 / 503
+/// 401 / 403 等鉴权和参数错误重试也不会成功，直接返回
+fn is_retryable(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if cause.is::<RateLimitedError>() {
+            return true;
+        }
+        if let Some(api) = cause.downcast_ref::<ApiStatusError>() {
+            return matches!(api.status, 500 | 502 | 503);
+        }
+        cause.downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request())
+    })
+}
+
+/// 第 retry 次重试前的等待：1s、2s、4s…；429 带 Retry-After 时取两者较大值
+fn retry_delay(retry: u32, err: &anyhow::Error) -> Duration {
+    let backoff_ms = RETRY_BASE_DELAY_MS.saturating_mul(1u64 << retry.min(16));
+    let retry_after_ms = err.chain()
+        .find_map(|cause| cause.downcast_ref::<RateLimitedError>())
+        .and_then(|e| e.retry_after_secs)
+        .map_or(0, |secs| secs.saturating_mul(1000));
+    Duration::from_millis(backoff_ms.max(retry_after_ms).min(RETRY_MAX_DELAY_SECS * 1000))
+}
+
+/// 执行一次请求，临时性失败时指数退避重试，最多重试 max_retries 次
+/// attempt 每次调用都重新构建请求（multipart::Form 不能复用）
+async fn with_retries<F, Fut>(max_retries: u32, mut attempt: F) -> Result<String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let mut retry = 0;
+    loop {
+        match attempt().await {
+            Err(e) if retry < max_retries && is_retryable(&e) => {
+                let delay = retry_delay(retry, &e);
+                retry += 1;
+                log::warn!(
+                    "云端识别失败: {}，{} ms 后第 {}/{} 次重试",
+                    e, delay.as_millis(), retry, max_retries
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
 // ===== OpenAI 兼容实现 =====
 
 /// POST /audio/transcriptions（multipart/form-data）
-/// 适用于 OpenAI / 火山引擎 / 讯飞 / 自定义
-async fn transcribe_openai_compatible(params: CloudTranscribeParams) -> Result<String> {
+/// 适用于 OpenAI / 火山引擎 / 讯飞 / Groq / 自定义
+async fn transcribe_openai_compatible(mut params: CloudTranscribeParams) -> Result<String> {
+    // Base URL 留空时使用服务商默认地址
+    let base_url = match params.base_url.trim() {
+        "" => default_base_url(&params.provider).unwrap_or_default(),
+        base => base,
+    };
     let url = format!(
         "{}/audio/transcriptions",
-        params.base_url.trim_end_matches('/')
+        base_url.trim_end_matches('/')
     );
     log::info!("OpenAI 兼容 ASR 请求: {}", url);
 
-    // 编码为 WAV
-    let wav_bytes = encode_wav(&params.audio_samples, 16000, 1);
-    log::info!("WAV 大小: {} 字节 ({:.1} KB)", wav_bytes.len(), wav_bytes.len() as f64 / 1024.0);
+    // 实时录音编码为 WAV，已编码的文件原样上传
+    // 取出音频后 params 其余字段仍用于每次请求
+    let audio = std::mem::replace(&mut params.audio, CloudAudio::Pcm(Vec::new()));
+    let (audio_bytes, mime, file_name) = audio.into_upload();
+    log::info!("音频大小: {} 字节 ({:.1} KB, {})", audio_bytes.len(), audio_bytes.len() as f64 / 1024.0, mime);
+    check_upload_size(audio_bytes.len(), params.max_upload_bytes)?;
+
+    let client = crate::net::client_builder()
+        .timeout(Duration::from_secs(120))
+        .build()
+        .context("创建 HTTP 客户端失败")?;
+
+    with_retries(params.max_retries, || {
+        openai_compatible_request(&client, &url, &params, &audio_bytes, &mime, &file_name)
+    }).await
+}
 
+/// 发送一次 OpenAI 兼容识别请求（每次重试都重新构建 multipart 表单）
+async fn openai_compatible_request(
+    client: &reqwest::Client,
+    url: &str,
+    params: &CloudTranscribeParams,
+    audio_bytes: &[u8],
+    mime: &str,
+    file_name: &str,
+) -> Result<String> {
     // 构建 multipart/form-data
-    let file_part = multipart::Part::bytes(wav_bytes)
-        .file_name("audio.wav")
-        .mime_str("audio/wav")
+    let file_part = multipart::Part::bytes(audio_bytes.to_vec())
+        .file_name(file_name.to_string())
+        .mime_str(mime)
         .context("设置 MIME 类型失败")?;
 
     let mut form = multipart::Form::new()
         .part("file", file_part)
-        .text("model", model_name_for_provider(&params.provider));
+        .text("model", effective_model_name(&params.provider, params.model.as_deref()));
 
     if params.language != "auto" && !params.language.is_empty() {
         form = form.text("language", params.language.clone());
     }
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(120))
-        .build()
-        .context("创建 HTTP 客户端失败")?;
+    // json 是 OpenAI 默认值，无需显式传
+    if !params.response_format.is_empty() && params.response_format != "json" {
+        form = form.text("response_format", params.response_format.clone());
+    }
 
     let resp = client
-        .post(&url)
+        .post(url)
         .header("Authorization", format!("Bearer {}", params.api_key))
         .multipart(form)
         .send()
         .await
         .context("HTTP 请求失败，请检查网络连接和 API 配置")?;
 
+    // 记录限流响应头（成功和失败的响应都可能携带）
+    let rate_limit = parse_rate_limit_headers(resp.headers());
+    if rate_limit.has_any() {
+        record_rate_limit(rate_limit.clone());
+    }
+
     let status = resp.status();
+    if status.as_u16() == 429 {
+        let body = resp.text().await.unwrap_or_default();
+        log::warn!("云端限流 (429): {}", body);
+        return Err(RateLimitedError {
+            retry_after_secs: rate_limit.retry_after_secs,
+            remaining_requests: rate_limit.remaining_requests,
+        }.into());
+    }
     if !status.is_success() {
         let body = resp.text().await.unwrap_or_default();
-        anyhow::bail!("API 错误 {}: {}", status.as_u16(), body);
+        return Err(ApiStatusError { status: status.as_u16(), body }.into());
     }
 
-    let result: OpenAITranscriptionResponse = resp
-        .json()
+    let content_type = resp.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    let body = resp.text()
         .await
-        .context("解析 API 响应失败")?;
+        .context("读取 API 响应失败")?;
 
-    let text = result.text.trim().to_string();
-    // 用 chars().take() 按字符截取，避免在多字节 UTF-8 字符中间切割导致 panic
-    let preview: String = text.chars().take(60).collect();
+    let text = parse_transcription_body(&body, content_type.as_deref())?;
+    let preview = crate::truncate_chars(&text, 60);
     log::info!("识别完成，结果: \"{}\"", preview);
     Ok(text)
 }
 
+// ===== 限流信息 =====
+
+/// 服务商通过响应头返回的限流信息（OpenAI: retry-after / x-ratelimit-*）
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitInfo {
+    /// 建议等待的秒数（Retry-After）
+    pub retry_after_secs: Option<u64>,
+    /// 当前窗口剩余请求数 / 上限
+    pub remaining_requests: Option<u64>,
+    pub limit_requests: Option<u64>,
+    /// 请求额度重置时间（服务商原样返回，如 "6m0s"）
+    pub reset_requests: Option<String>,
+    /// 记录时间
+    pub observed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl RateLimitInfo {
+    fn has_any(&self) -> bool {
+        self.retry_after_secs.is_some()
+            || self.remaining_requests.is_some()
+            || self.limit_requests.is_some()
+            || self.reset_requests.is_some()
+    }
+}
+
+/// 429 限流错误：调用方可 downcast 取出等待时间
+#[derive(Debug, Clone)]
+pub struct RateLimitedError {
+    pub retry_after_secs: Option<u64>,
+    pub remaining_requests: Option<u64>,
+}
+
+impl std::fmt::Display for RateLimitedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.retry_after_secs {
+            Some(secs) => write!(f, "请求频率超限，请 {} 秒后重试", secs)?,
+            None => write!(f, "请求频率超限，请稍后重试")?,
+        }
+        if let Some(remaining) = self.remaining_requests {
+            write!(f, "（当前剩余请求数: {}）", remaining)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RateLimitedError {}
+
+/// 最近一次云端响应中的限流信息，供 get_cloud_quota 查询
+static LAST_RATE_LIMIT: Lazy<RwLock<Option<RateLimitInfo>>> = Lazy::new(|| RwLock::new(None));
+
+fn record_rate_limit(mut info: RateLimitInfo) {
+    info.observed_at = Some(chrono::Utc::now());
+    if let Ok(mut guard) = LAST_RATE_LIMIT.write() {
+        *guard = Some(info);
+    }
+}
+
+/// 最近一次观察到的限流信息（服务商未返回限流头时为 None）
+pub fn last_rate_limit() -> Option<RateLimitInfo> {
+    LAST_RATE_LIMIT.read().ok().and_then(|guard| guard.clone())
+}
+
+/// 解析限流相关响应头
+/// Retry-After 支持秒数和 HTTP 日期两种格式
+pub fn parse_rate_limit_headers(headers: &reqwest::header::HeaderMap) -> RateLimitInfo {
+    let get = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(|v| v.trim().to_string());
+    let get_u64 = |name: &str| get(name).and_then(|v| v.parse::<u64>().ok());
+
+    let retry_after_secs = get("retry-after").and_then(|v| {
+        v.parse::<u64>().ok().or_else(|| {
+            chrono::DateTime::parse_from_rfc2822(&v).ok().map(|at| {
+                (at.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds().max(0) as u64
+            })
+        })
+    });
+
+    RateLimitInfo {
+        retry_after_secs,
+        remaining_requests: get_u64("x-ratelimit-remaining-requests"),
+        limit_requests: get_u64("x-ratelimit-limit-requests"),
+        reset_requests: get("x-ratelimit-reset-requests"),
+        observed_at: None,
+    }
+}
+
+/// 上传大小检查：16kHz 16-bit WAV 约 1.9MB/分钟，25MB 约 13 分钟
+///
+/// 超限直接报错，避免服务端返回难以理解的 413 错误。
+/// 如需上传更长录音，可改用 FLAC/MP3 等压缩编码（体积约为 WAV 的 1/2 ~ 1/10）
+fn check_upload_size(size: usize, max_bytes: u64) -> Result<()> {
+    if max_bytes > 0 && size as u64 > max_bytes {
+        anyhow::bail!(
+            "录音过长，超出云端大小限制（{:.1} MB > {:.1} MB），请缩短录音或使用本地识别",
+            size as f64 / 1024.0 / 1024.0,
+            max_bytes as f64 / 1024.0 / 1024.0
+        );
+    }
+    Ok(())
+}
+
+/// 解析 OpenAI 兼容接口的响应体
+///
+/// response_format=json 时返回 {"text": "..."}；
+/// response_format=text 时部分服务直接返回裸字符串。
+/// 先按 Content-Type / 内容形态判断，JSON 解析失败时退回纯文本
+fn parse_transcription_body(body: &str, content_type: Option<&str>) -> Result<String> {
+    let trimmed = body.trim();
+    let looks_json = content_type.map(|ct| ct.contains("json")).unwrap_or(false)
+        || trimmed.starts_with('{');
+
+    if looks_json {
+        match serde_json::from_str::<OpenAITranscriptionResponse>(trimmed) {
+            Ok(result) => return Ok(result.text.trim().to_string()),
+            Err(e) if trimmed.starts_with('{') => {
+                // 是 JSON 但没有 text 字段，不能当作转写结果
+                anyhow::bail!("解析 API 响应失败: {}（响应: {}）", e, trimmed);
+            }
+            Err(_) => {} // Content-Type 声称 JSON 但内容是纯文本，按纯文本处理
+        }
+    }
+
+    // 部分服务返回 JSON 字符串（如 "hello"），去掉引号并还原转义
+    if trimmed.starts_with('"') {
+        if let Ok(serde_json::Value::String(text)) = serde_json::from_str(trimmed) {
+            return Ok(text.trim().to_string());
+        }
+    }
+
+    Ok(trimmed.to_string())
+}
+
 // ===== 阿里云 NLS 实现 =====
 
 /// 阿里云 NLS 一句话识别 RESTful API
@@ -188,15 +601,34 @@ async fn transcribe_aliyun_nls(params: &CloudTranscribeParams) -> Result<String>
     }
 
     // 编码音频为 WAV（16-bit PCM，单声道，16 kHz，满足阿里云 NLS 要求）
-    let wav_bytes = encode_wav(&params.audio_samples, 16000, 1);
-    log::info!("阿里云 NLS 请求，AppKey={}, WAV={} 字节", appkey, wav_bytes.len());
+    let (wav_bytes, sample_rate) = match &params.audio {
+        CloudAudio::Pcm(samples) => {
+            // 一句话识别只接受 60 秒以内的音频
+            if samples.len() > ALIYUN_NLS_MAX_SECS * 16000 {
+                anyhow::bail!(
+                    "录音过长，超出云端大小限制：阿里云一句话识别最长 {} 秒，请缩短录音",
+                    ALIYUN_NLS_MAX_SECS
+                );
+            }
+            (encode_wav(samples, 16000, 1), 16000)
+        }
+        // 阿里云 NLS 只接受 8k / 16kHz PCM / WAV，压缩格式需先解码；上传的 WAV 按文件头校验时长和采样率
+        CloudAudio::Encoded { bytes, mime, .. } if mime == "audio/wav" => {
+            let info = check_wav_upload(bytes, ALIYUN_NLS_MAX_SECS, &[8000, 16000], "阿里云一句话识别")?;
+            (bytes.clone(), info.sample_rate)
+        }
+        CloudAudio::Encoded { mime, .. } => {
+            anyhow::bail!("阿里云一句话识别不支持 {} 格式，请使用 16kHz WAV 或改用本地识别", mime)
+        }
+    };
+    log::info!("阿里云 NLS 请求，AppKey={}, WAV={} 字节, {} Hz", appkey, wav_bytes.len(), sample_rate);
 
     let url = format!(
-        "https://nls-gateway-cn-shanghai.aliyuncs.com/stream/v1/asr?appkey={}",
-        appkey
+        "https://nls-gateway-cn-shanghai.aliyuncs.com/stream/v1/asr?appkey={}&format=wav&sample_rate={}",
+        appkey, sample_rate
     );
 
-    let client = reqwest::Client::builder()
+    let client = crate::net::client_builder()
         .timeout(Duration::from_secs(120))
         .build()
         .context("创建 HTTP 客户端失败")?;
@@ -210,6 +642,13 @@ async fn transcribe_aliyun_nls(params: &CloudTranscribeParams) -> Result<String>
         .await
         .context("阿里云 NLS 请求失败，请检查网络和 AppKey/Token")?;
 
+    // 网关故障时返回的不是 JSON，单独报告状态码（可重试）
+    let status = resp.status().as_u16();
+    if matches!(status, 500 | 502 | 503) {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(ApiStatusError { status, body }.into());
+    }
+
     let nls: NlsResponse = resp
         .json()
         .await
@@ -217,7 +656,7 @@ async fn transcribe_aliyun_nls(params: &CloudTranscribeParams) -> Result<String>
 
     if nls.status == 20000000 {
         let text = nls.result.unwrap_or_default();
-        let preview: String = text.chars().take(60).collect();
+        let preview = crate::truncate_chars(&text, 60);
         log::info!("阿里云 NLS 识别完成: \"{}\"", preview);
         Ok(text)
     } else {
@@ -226,6 +665,320 @@ async fn transcribe_aliyun_nls(params: &CloudTranscribeParams) -> Result<String>
     }
 }
 
+// ===== Deepgram 实现 =====
+
+/// POST /listen（请求体为音频字节，Authorization: Token <key>）
+///
+/// 响应格式：
+/// ```json
+/// {"results":{"channels":[{"alternatives":[{"transcript":"北京的天气","confidence":0.98}]}]}}
+/// ```
+/// 没有识别到语音时 alternatives 为空，返回空字符串
+async fn transcribe_deepgram(mut params: CloudTranscribeParams) -> Result<String> {
+    let base_url = match params.base_url.trim() {
+        "" => default_base_url(&params.provider).unwrap_or_default(),
+        base => base,
+    };
+    let mut url = reqwest::Url::parse(&format!("{}/listen", base_url.trim_end_matches('/')))
+        .context("Deepgram Base URL 无效")?;
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("model", &effective_model_name(&params.provider, params.model.as_deref()));
+        query.append_pair("smart_format", "true");
+        if params.language == "auto" || params.language.is_empty() {
+            query.append_pair("detect_language", "true");
+        } else {
+            query.append_pair("language", &params.language);
+        }
+    }
+    log::info!("Deepgram ASR 请求: {}", url);
+
+    // 实时录音编码为 WAV，已编码的文件原样上传（Deepgram 按 Content-Type 识别格式）
+    let audio = std::mem::replace(&mut params.audio, CloudAudio::Pcm(Vec::new()));
+    let (audio_bytes, mime, _) = audio.into_upload();
+    log::info!("音频大小: {} 字节 ({:.1} KB, {})", audio_bytes.len(), audio_bytes.len() as f64 / 1024.0, mime);
+    check_upload_size(audio_bytes.len(), params.max_upload_bytes)?;
+
+    let client = crate::net::client_builder()
+        .timeout(Duration::from_secs(120))
+        .build()
+        .context("创建 HTTP 客户端失败")?;
+
+    with_retries(params.max_retries, || {
+        deepgram_request(&client, url.as_str(), &params.api_key, &audio_bytes, &mime)
+    }).await
+}
+
+/// 发送一次 Deepgram 识别请求
+async fn deepgram_request(
+    client: &reqwest::Client,
+    url: &str,
+    api_key: &str,
+    audio_bytes: &[u8],
+    mime: &str,
+) -> Result<String> {
+    let resp = client
+        .post(url)
+        .header("Authorization", format!("Token {}", api_key.trim()))
+        .header(reqwest::header::CONTENT_TYPE, mime)
+        .body(audio_bytes.to_vec())
+        .send()
+        .await
+        .context("Deepgram 请求失败，请检查网络连接和 API Key")?;
+
+    let rate_limit = parse_rate_limit_headers(resp.headers());
+    if rate_limit.has_any() {
+        record_rate_limit(rate_limit.clone());
+    }
+
+    let status = resp.status();
+    if status.as_u16() == 429 {
+        let body = resp.text().await.unwrap_or_default();
+        log::warn!("云端限流 (429): {}", body);
+        return Err(RateLimitedError {
+            retry_after_secs: rate_limit.retry_after_secs,
+            remaining_requests: rate_limit.remaining_requests,
+        }.into());
+    }
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(ApiStatusError { status: status.as_u16(), body }.into());
+    }
+
+    let body = resp.text()
+        .await
+        .context("读取 Deepgram 响应失败")?;
+    let text = parse_deepgram_body(&body)?;
+    let preview = crate::truncate_chars(&text, 60);
+    log::info!("Deepgram 识别完成: \"{}\"", preview);
+    Ok(text)
+}
+
+/// 解析 Deepgram 响应，取第一个声道的首选结果（没有识别到语音时为空字符串）
+fn parse_deepgram_body(body: &str) -> Result<String> {
+    let resp: DeepgramResponse = serde_json::from_str(body)
+        .with_context(|| format!("解析 Deepgram 响应失败: {}", crate::truncate_chars(body, 200)))?;
+    Ok(resp.results.channels
+        .into_iter()
+        .next()
+        .and_then(|channel| channel.alternatives.into_iter().next())
+        .map(|alt| alt.transcript.trim().to_string())
+        .unwrap_or_default())
+}
+
+/// 测试 Deepgram 连通性：GET /projects 验证 API Key
+pub async fn test_deepgram(base_url: &str, api_key: &str) -> Result<String, String> {
+    if api_key.trim().is_empty() {
+        return Err("请先填写 API Key".to_string());
+    }
+    let base_url = match base_url.trim() {
+        "" => default_base_url(&CloudProvider::Deepgram).unwrap_or_default(),
+        base => base,
+    };
+    let url = format!("{}/projects", base_url.trim_end_matches('/'));
+    log::info!("测试 Deepgram 连接: {}", url);
+
+    let client = crate::net::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("Token {}", api_key.trim()))
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                "连接超时，请检查网络或 Base URL".to_string()
+            } else if e.is_connect() {
+                "无法连接到服务器，请检查 Base URL 和网络".to_string()
+            } else {
+                format!("请求失败: {}", e)
+            }
+        })?;
+
+    match resp.status().as_u16() {
+        200..=299 => Ok("连接成功（API Key 有效）".to_string()),
+        401 | 403 => Err("API Key 无效或权限不足".to_string()),
+        429       => Err("请求频率超限，稍后再试".to_string()),
+        code      => Err(format!("服务返回异常状态: {}", code)),
+    }
+}
+
+// ===== Azure 实现 =====
+
+/// POST https://<region>.stt.speech.microsoft.com/speech/recognition/conversation/cognitiveservices/v1
+///
+/// 响应格式：
+/// ```json
+/// {"RecognitionStatus":"Success","DisplayText":"北京的天气。","Offset":0,"Duration":12300000}
+/// ```
+///
+/// 字段约定：
+/// - `params.base_url` → 区域（如 eastasia）
+/// - `params.api_key`  → 语音服务资源的密钥（Ocp-Apim-Subscription-Key）
+async fn transcribe_azure(params: &CloudTranscribeParams) -> Result<String> {
+    let region = params.base_url.trim();
+    let key = params.api_key.trim();
+
+    if region.is_empty() {
+        anyhow::bail!("Azure 语音服务：请在区域字段填写资源所在区域（如 eastasia）");
+    }
+    if key.is_empty() {
+        anyhow::bail!("Azure 语音服务：请在 API Key 字段填写资源密钥");
+    }
+
+    // 短音频接口必须指定语言；自动检测时按中文识别
+    let locale = match params.language.as_str() {
+        "" | "auto" => {
+            log::info!("Azure 短音频接口不支持自动检测语言，按 zh-CN 识别");
+            "zh-CN"
+        }
+        lang => azure_locale(lang)
+            .with_context(|| format!("Azure 语音服务不支持识别语言 {}", lang))?,
+    };
+
+    let (wav_bytes, sample_rate) = match &params.audio {
+        CloudAudio::Pcm(samples) => {
+            if samples.len() > AZURE_SHORT_AUDIO_MAX_SECS * 16000 {
+                anyhow::bail!(
+                    "录音过长，超出云端大小限制：Azure 短音频识别最长 {} 秒，请缩短录音",
+                    AZURE_SHORT_AUDIO_MAX_SECS
+                );
+            }
+            (encode_wav(samples, 16000, 1), 16000)
+        }
+        // 上传的 WAV 按文件头校验时长，Content-Type 使用文件的实际采样率
+        CloudAudio::Encoded { bytes, mime, .. } if mime == "audio/wav" => {
+            let info = check_wav_upload(bytes, AZURE_SHORT_AUDIO_MAX_SECS, &[8000, 16000], "Azure 短音频识别")?;
+            (bytes.clone(), info.sample_rate)
+        }
+        CloudAudio::Encoded { mime, .. } => {
+            anyhow::bail!("Azure 短音频识别不支持 {} 格式，请使用 16kHz WAV 或改用本地识别", mime)
+        }
+    };
+
+    let mut url = reqwest::Url::parse(&format!(
+        "https://{}.stt.speech.microsoft.com/speech/recognition/conversation/cognitiveservices/v1",
+        region
+    ))
+    .with_context(|| format!("Azure 区域无效: {}", region))?;
+    url.query_pairs_mut()
+        .append_pair("language", locale)
+        .append_pair("format", "simple");
+    log::info!("Azure 语音识别请求: {}，WAV={} 字节", url, wav_bytes.len());
+
+    let client = crate::net::client_builder()
+        .timeout(Duration::from_secs(120))
+        .build()
+        .context("创建 HTTP 客户端失败")?;
+
+    let resp = client
+        .post(url)
+        .header("Ocp-Apim-Subscription-Key", key)
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            format!("audio/wav; codecs=audio/pcm; samplerate={}", sample_rate),
+        )
+        .body(wav_bytes)
+        .send()
+        .await
+        .context("Azure 语音服务请求失败，请检查网络和区域")?;
+
+    // 记录限流响应头（成功和失败的响应都可能携带）
+    let rate_limit = parse_rate_limit_headers(resp.headers());
+    if rate_limit.has_any() {
+        record_rate_limit(rate_limit.clone());
+    }
+
+    let status = resp.status();
+    if status.as_u16() == 429 {
+        return Err(RateLimitedError {
+            retry_after_secs: rate_limit.retry_after_secs,
+            remaining_requests: rate_limit.remaining_requests,
+        }.into());
+    }
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(ApiStatusError { status: status.as_u16(), body }.into());
+    }
+
+    let azure: AzureResponse = resp
+        .json()
+        .await
+        .context("解析 Azure 响应失败")?;
+
+    match azure.recognition_status.as_str() {
+        "Success" => {
+            let preview = crate::truncate_chars(&azure.display_text, 60);
+            log::info!("Azure 识别完成: \"{}\"", preview);
+            Ok(azure.display_text)
+        }
+        "NoMatch" | "InitialSilenceTimeout" => anyhow::bail!("Azure 未识别到语音内容"),
+        status => anyhow::bail!("Azure 识别失败（{}）", status),
+    }
+}
+
+/// Whisper 语言代码 → Azure 识别语言区域（不支持的语言返回 None）
+fn azure_locale(language: &str) -> Option<&'static str> {
+    AZURE_LOCALES.iter()
+        .find(|(code, _)| *code == language)
+        .map(|(_, locale)| *locale)
+}
+
+/// Azure 支持的常用识别语言（Whisper 语言代码, Azure 区域）
+const AZURE_LOCALES: &[(&str, &str)] = &[
+    ("zh", "zh-CN"),
+    ("yue", "zh-HK"),
+    ("en", "en-US"),
+    ("ja", "ja-JP"),
+    ("ko", "ko-KR"),
+    ("fr", "fr-FR"),
+    ("de", "de-DE"),
+    ("es", "es-ES"),
+    ("it", "it-IT"),
+    ("pt", "pt-BR"),
+    ("ru", "ru-RU"),
+];
+
+/// 测试 Azure 语音服务连通性：用密钥换取访问令牌，验证区域和密钥
+pub async fn test_azure(region: &str, key: &str) -> Result<String, String> {
+    if region.trim().is_empty() { return Err("请填写区域（如 eastasia）".to_string()); }
+    if key.trim().is_empty()    { return Err("请先填写 API Key".to_string()); }
+
+    let url = format!(
+        "https://{}.api.cognitive.microsoft.com/sts/v1.0/issueToken",
+        region.trim()
+    );
+
+    let client = crate::net::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("创建客户端失败: {}", e))?;
+
+    let resp = client
+        .post(&url)
+        .header("Ocp-Apim-Subscription-Key", key.trim())
+        .body(vec![])
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_connect() {
+                "无法连接到服务器，请检查区域和网络".to_string()
+            } else {
+                format!("连接失败: {}", e)
+            }
+        })?;
+
+    match resp.status().as_u16() {
+        200..=299 => Ok("连接成功（区域和密钥有效）".to_string()),
+        401 | 403 => Err("密钥无效，或与该区域的资源不匹配".to_string()),
+        429       => Err("请求频率超限，稍后再试".to_string()),
+        code      => Err(format!("服务返回异常状态: {}", code)),
+    }
+}
+
 /// 测试阿里云 NLS 连通性
 ///
 /// 发送空 body 请求，通过错误码判断鉴权是否通过：
@@ -241,7 +994,7 @@ pub async fn test_aliyun_nls(appkey: &str, token: &str) -> Result<String, String
         appkey.trim()
     );
 
-    let client = reqwest::Client::builder()
+    let client = crate::net::client_builder()
         .timeout(Duration::from_secs(10))
         .build()
         .map_err(|e| format!("创建客户端失败: {}", e))?;
@@ -280,13 +1033,147 @@ pub async fn test_aliyun_nls(appkey: &str, token: &str) -> Result<String, String
     }
 }
 
-/// 各 OpenAI 兼容服务商对应的 model 参数
+/// 各服务商支持的识别语言（None = 多语言服务，不做限制）
+///
+/// 仅用于提示：服务商实际支持范围可能随模型 / 账号配置变化，不据此阻止识别
+fn provider_languages(provider: &CloudProvider) -> Option<&'static [&'static str]> {
+    match provider {
+        CloudProvider::OpenAI     => None,
+        CloudProvider::VolcEngine => Some(&["zh", "en", "yue", "ja", "ko"]),
+        CloudProvider::Aliyun     => Some(&["zh", "en", "yue"]),
+        CloudProvider::Xunfei     => Some(&["zh", "en"]),
+        CloudProvider::Groq       => None,
+        CloudProvider::Deepgram   => None,
+        CloudProvider::Azure      => Some(&["zh", "yue", "en", "ja", "ko", "fr", "de", "es", "it", "pt", "ru"]),
+        CloudProvider::Custom     => None,
+    }
+}
+
+/// 服务商是否支持该识别语言（auto / 空值视为支持）
+pub fn provider_supports_language(provider: &CloudProvider, language: &str) -> bool {
+    if matches!(language, "" | "auto") {
+        return true;
+    }
+    match provider_languages(provider) {
+        Some(langs) => langs.contains(&language),
+        None => true,
+    }
+}
+
+/// 服务商与识别语言不匹配时返回提示文字
+pub fn provider_language_advice(provider: &CloudProvider, language: &str) -> Option<String> {
+    if provider_supports_language(provider, language) {
+        return None;
+    }
+    let langs = provider_languages(provider).unwrap_or_default();
+    Some(format!(
+        "{:?} 可能不支持识别语言 {}（支持：{}），识别结果可能不准确",
+        provider, language, langs.join(" / ")
+    ))
+}
+
+/// 实际使用的 model 参数：用户填写了覆盖值则优先使用，否则取服务商默认
+pub fn effective_model_name(provider: &CloudProvider, model_override: Option<&str>) -> String {
+    match model_override.map(str::trim) {
+        Some(m) if !m.is_empty() => m.to_string(),
+        _ => model_name_for_provider(provider),
+    }
+}
+
+/// 各服务商对应的 model 参数（OpenAI 兼容服务和 Deepgram）
 fn model_name_for_provider(provider: &CloudProvider) -> String {
     match provider {
         CloudProvider::OpenAI    => "whisper-1".to_string(),
         CloudProvider::VolcEngine => "Doubao-asr".to_string(),
         CloudProvider::Aliyun   => "paraformer-realtime-v2".to_string(), // 备用（NLS 不用 model）
         CloudProvider::Xunfei   => "iflytekws".to_string(),
+        CloudProvider::Groq     => "whisper-large-v3".to_string(),
+        CloudProvider::Deepgram => "nova-2".to_string(),
+        CloudProvider::Azure    => "azure-stt".to_string(),      // 备用（短音频接口不用 model）
         CloudProvider::Custom   => "whisper-1".to_string(),
     }
 }
+
+/// 各服务商的默认 Base URL（阿里云填 AppKey、自定义由用户填写，返回 None）
+pub fn default_base_url(provider: &CloudProvider) -> Option<&'static str> {
+    match provider {
+        CloudProvider::OpenAI => Some("https://api.openai.com/v1"),
+        CloudProvider::Groq   => Some("https://api.groq.com/openai/v1"),
+        CloudProvider::Deepgram => Some("https://api.deepgram.com/v1"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_json_body_reads_text_field() {
+        let body = r#"{"text":" 北京的天气 "}"#;
+        assert_eq!(parse_transcription_body(body, Some("application/json")).unwrap(), "北京的天气");
+        // 没有 Content-Type 时按内容判断
+        assert_eq!(parse_transcription_body(body, None).unwrap(), "北京的天气");
+    }
+
+    #[test]
+    fn parse_json_body_without_text_is_error() {
+        assert!(parse_transcription_body(r#"{"error":"bad"}"#, Some("application/json")).is_err());
+    }
+
+    #[test]
+    fn parse_raw_text_body() {
+        assert_eq!(parse_transcription_body("hello world\n", Some("text/plain")).unwrap(), "hello world");
+        // Content-Type 声称 JSON 但内容是纯文本
+        assert_eq!(parse_transcription_body("hello", Some("application/json")).unwrap(), "hello");
+    }
+
+    #[test]
+    fn parse_bare_json_string_body_strips_quotes() {
+        assert_eq!(parse_transcription_body(r#""hello""#, Some("application/json")).unwrap(), "hello");
+        assert_eq!(parse_transcription_body(r#""你好\n""#, None).unwrap(), "你好");
+    }
+
+    /// n 个静音样本
+    fn silence(n: usize) -> Vec<f32> {
+        vec![0.0; n]
+    }
+
+    #[test]
+    fn parse_wav_header_reads_rate_and_duration() {
+        let wav = encode_wav(&silence(8000 * 3), 8000, 1);
+        let info = parse_wav_header(&wav).unwrap();
+        assert_eq!(info.sample_rate, 8000);
+        assert_eq!(info.channels, 1);
+        assert_eq!(info.bits_per_sample, 16);
+        assert!((info.duration_secs - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parse_wav_header_skips_extra_chunks() {
+        let wav = encode_wav(&silence(16000), 16000, 1);
+        // 在 fmt 与 data 之间插入一个 LIST 块（奇数长度，带对齐填充）
+        let mut with_list = wav[..36].to_vec();
+        with_list.extend_from_slice(b"LIST");
+        with_list.extend_from_slice(&3u32.to_le_bytes());
+        with_list.extend_from_slice(&[1, 2, 3, 0]);
+        with_list.extend_from_slice(&wav[36..]);
+        let info = parse_wav_header(&with_list).unwrap();
+        assert_eq!(info.sample_rate, 16000);
+        assert!((info.duration_secs - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn check_wav_upload_enforces_duration_and_rate() {
+        let long = encode_wav(&silence(16000 * 61), 16000, 1);
+        assert!(check_wav_upload(&long, 60, &[8000, 16000], "Azure").is_err());
+
+        let wrong_rate = encode_wav(&silence(44100), 44100, 1);
+        assert!(check_wav_upload(&wrong_rate, 60, &[8000, 16000], "Azure").is_err());
+
+        let ok = encode_wav(&silence(8000 * 10), 8000, 1);
+        assert_eq!(check_wav_upload(&ok, 60, &[8000, 16000], "Azure").unwrap().sample_rate, 8000);
+
+        assert!(parse_wav_header(b"not a wav file").is_err());
+    }
+}
@@ -1,8 +1,12 @@
 // commands/audio.rs - 录音相关的 Tauri 命令
 
-use tauri::State;
+use tauri::{State, Emitter, Manager};
 use serde::Serialize;
-use crate::state::{AppState, RecordingStatus};
+use crate::state::{AppState, InnerState, RecordingStatus};
+
+/// Processing 状态看门狗在推理超时上限之外的余量（秒）
+/// 覆盖模型加载、云端请求等，超过后认为识别流程已丢失
+const PROCESSING_WATCHDOG_MARGIN_SECS: u64 = 60;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -22,18 +26,33 @@ pub struct RecordingStatusResponse {
 /// 关键 Rust 规则：标准 Mutex 的 guard 不能跨越 .await 点
 /// 所以每次拿锁都在独立的块 { } 里，用完立即 drop
 #[tauri::command]
-pub async fn start_recording(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn start_recording(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    begin_recording(&state, &app)
+}
+
+/// 开始录音的实际逻辑，供 start_recording 命令和连续听写自动重启共用
+/// 录音流启动后同时启动录音监视任务（静音自动停止等）
+pub(crate) fn begin_recording(state: &AppState, app: &tauri::AppHandle) -> Result<(), String> {
     // ---- 第一步：检查并更新业务状态 ----
-    {
+    let (session, meter) = {
         let mut inner = state.inner.lock()
             .map_err(|e| format!("状态锁失败: {}", e))?;
 
+        recover_stuck_processing(&mut inner);
+
         if inner.recording_status == RecordingStatus::Recording {
             return Err("已在录音中".to_string());
         }
         inner.recording_status = RecordingStatus::Recording;
         inner.audio_buffer = None; // 清空上次录音数据
-    } // ← 锁在这里自动释放，不跨越 await
+        inner.channel_buffers = None;
+        inner.capture_timing = None;
+        inner.recording_session += 1;
+        (inner.recording_session, level_meter(&inner.settings))
+    }; // ← 锁在这里自动释放，不跨越 await
 
     // ---- 第二步：启动 cpal 录音流 ----
     {
@@ -47,9 +66,12 @@ pub async fn start_recording(state: State<'_, AppState>) -> Result<(), String> {
             }
             return Err(format!("启动录音失败: {}", e));
         }
+        recorder.set_level_sender(Some(spawn_level_emitter(app.clone(), "audio-level", meter)));
     }
 
     log::info!("cpal 录音流已启动");
+
+    spawn_recording_monitor(app.clone(), session);
     Ok(())
 }
 
@@ -61,21 +83,37 @@ pub async fn start_recording(state: State<'_, AppState>) -> Result<(), String> {
 /// 3. 状态改为 Processing
 #[tauri::command]
 pub async fn stop_recording(state: State<'_, AppState>) -> Result<StopRecordingResponse, String> {
+    finish_recording(&state)
+}
+
+/// 停止录音的实际逻辑，供 stop_recording 命令和自动停止（静音检测）共用
+pub(crate) fn finish_recording(state: &AppState) -> Result<StopRecordingResponse, String> {
     // ---- 第一步：检查状态 ----
-    {
-        let inner = state.inner.lock()
+    // 检查与切换到 Processing 在同一次加锁内完成，
+    // 避免手动停止与自动停止同时触发时重复 stop
+    let (min_recording_ms, discard_short, preserve_channels) = {
+        let mut inner = state.inner.lock()
             .map_err(|e| format!("状态锁失败: {}", e))?;
         if inner.recording_status != RecordingStatus::Recording {
             return Err("当前未在录音".to_string());
         }
-    }
+        inner.recording_status = RecordingStatus::Processing;
+        inner.processing_since = Some(std::time::Instant::now());
+        (
+            inner.settings.min_recording_ms,
+            inner.settings.discard_short_recordings,
+            inner.settings.preserve_channels,
+        )
+    };
 
     // ---- 第二步：停止录音，取回 PCM 数据 ----
     // stop() 会 drop cpal::Stream（停止采集），返回缓冲区数据
-    let audio_data: Vec<f32> = {
+    let (audio_data, channel_data, device_error, capture_timing) = {
         let mut recorder = state.recorder.lock()
             .map_err(|e| format!("录音器锁失败: {}", e))?;
-        recorder.stop()
+        let device_error = recorder.stream_error();
+        let (mono, channels) = recorder.stop_with_channels(preserve_channels);
+        (mono, channels, device_error, recorder.last_capture_timing())
     };
 
     let sample_count = audio_data.len();
@@ -83,22 +121,68 @@ pub async fn stop_recording(state: State<'_, AppState>) -> Result<StopRecordingR
 
     log::info!("录音停止，采集 {} 样本，{} ms", sample_count, duration_ms);
 
+    // 录音中设备出错（如 USB 麦克风被拔出）：
+    // 断开前一段音频都没有采到时直接报错；否则保留已采集部分，由前端提示后照常识别
+    if let Some(err) = &device_error {
+        log::warn!("录音期间设备出错: {}", err);
+        if sample_count == 0 {
+            let mut inner = state.inner.lock()
+                .map_err(|e| format!("状态锁失败: {}", e))?;
+            inner.recording_status = RecordingStatus::Idle;
+            inner.processing_since = None;
+            return Err(err.clone());
+        }
+    }
+
+    // 录音过短（误触快捷键）：不进入识别，直接复位
+    if duration_ms < min_recording_ms {
+        log::info!("录音过短（{} ms < {} ms），已丢弃", duration_ms, min_recording_ms);
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.recording_status = RecordingStatus::Idle;
+        inner.processing_since = None;
+        if !discard_short {
+            return Err(format!("录音过短（{} ms），请按住快捷键说话", duration_ms));
+        }
+        return Ok(StopRecordingResponse { sample_count, duration_ms, device_error, capture_timing, discarded: true });
+    }
+
     // ---- 第三步：存数据，更新状态 ----
     {
         let mut inner = state.inner.lock()
             .map_err(|e| format!("状态锁失败: {}", e))?;
         inner.audio_buffer = Some(audio_data);
-        inner.recording_status = RecordingStatus::Processing;
+        inner.channel_buffers = channel_data;
+        inner.capture_timing = capture_timing.clone();
     }
 
-    Ok(StopRecordingResponse { sample_count, duration_ms })
+    Ok(StopRecordingResponse { sample_count, duration_ms, device_error, capture_timing, discarded: false })
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct StopRecordingResponse {
     pub sample_count: usize,
     pub duration_ms: u64,
+    /// 录音期间设备出错（如"录音设备已断开"），音频只包含出错前采集的部分
+    pub device_error: Option<String>,
+    /// 采集时长与墙钟时长对比（drifted 为 true 时前端可提示时间戳可能不准）
+    pub capture_timing: Option<crate::audio::CaptureTiming>,
+    /// 录音过短已被静默丢弃（前端无需再调用识别）
+    pub discarded: bool,
+}
+
+/// Processing 看门狗：超过推理超时上限 + 余量仍未结束则强制复位为 Idle
+/// 超时上限为 0（不限制超时）时不自动复位，只能取消识别或手动复位
+fn recover_stuck_processing(inner: &mut crate::state::InnerState) {
+    let cap = inner.settings.inference_timeout_cap_secs;
+    if cap == 0 {
+        return;
+    }
+    let watchdog_secs = cap + PROCESSING_WATCHDOG_MARGIN_SECS;
+    if inner.recover_stuck_processing(watchdog_secs) {
+        log::warn!("Processing 状态超过 {} 秒未结束，已强制复位为 Idle", watchdog_secs);
+    }
 }
 
 /// 查询当前录音状态
@@ -106,9 +190,11 @@ pub struct StopRecordingResponse {
 pub async fn get_recording_status(
     state: State<'_, AppState>,
 ) -> Result<RecordingStatusResponse, String> {
-    let inner = state.inner.lock()
+    let mut inner = state.inner.lock()
         .map_err(|e| format!("状态锁失败: {}", e))?;
 
+    recover_stuck_processing(&mut inner);
+
     let sample_count = inner.audio_buffer.as_ref().map(|b| b.len()).unwrap_or(0);
 
     Ok(RecordingStatusResponse {
@@ -117,3 +203,540 @@ pub async fn get_recording_status(
         sample_count,
     })
 }
+
+/// 手动复位录音/识别状态（应急出口）
+///
+/// 停止可能残留的录音流，清空缓冲区，状态强制回到 Idle。
+/// 用于界面卡在"识别中"且看门狗尚未触发的情况。
+#[tauri::command]
+pub async fn reset_state(state: State<'_, AppState>) -> Result<(), String> {
+    {
+        let mut recorder = state.recorder.lock()
+            .map_err(|e| format!("录音器锁失败: {}", e))?;
+        if recorder.is_recording() {
+            let _ = recorder.stop();
+        }
+    }
+
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.recording_status = RecordingStatus::Idle;
+        inner.audio_buffer = None;
+        inner.channel_buffers = None;
+        inner.capture_timing = None;
+        inner.processing_since = None;
+        inner.continuous_active = false;
+    }
+
+    log::info!("录音状态已手动复位为 Idle");
+    Ok(())
+}
+
+// ===== 录音监视（静音自动停止 / 设备错误通知）=====
+
+/// 监视任务轮询间隔（毫秒）
+const MONITOR_INTERVAL_MS: u64 = 100;
+
+/// 计算实时音量的窗口长度（毫秒）
+const MONITOR_RMS_WINDOW_MS: u64 = 300;
+
+/// 每次开始录音时启动的后台监视任务
+///
+/// 静音自动停止（auto_stop_silence_ms > 0）：检测到说话后，
+/// 音量连续低于阈值达到设定时长即自动停止并识别。
+/// 开始说话前的静音不计时，避免刚开始录音就被误停。
+///
+/// 时长上限（max_recording_secs > 0）：录音超过上限时自动停止，
+/// 发送 recording-limit-reached 事件，已录部分照常识别。
+///
+/// session：本次录音的序号，录音结束或已开始新一轮录音时任务退出
+fn spawn_recording_monitor(app: tauri::AppHandle, session: u64) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let mut speech_detected = false;
+        let mut silence_ms: u64 = 0;
+        let mut error_reported = false;
+
+        loop {
+            tokio::time::sleep(std::time::Duration::from_millis(MONITOR_INTERVAL_MS)).await;
+
+            let (silence_limit_ms, threshold, max_secs) = {
+                let Ok(inner) = state.inner.lock() else { return };
+                if inner.recording_status != RecordingStatus::Recording
+                    || inner.recording_session != session
+                {
+                    return;
+                }
+                (
+                    inner.settings.auto_stop_silence_ms,
+                    inner.settings.auto_stop_threshold,
+                    inner.settings.max_recording_secs,
+                )
+            };
+
+            let (rms, stream_error, recorded_ms) = match state.recorder.lock() {
+                Ok(recorder) => {
+                    let frames = recorder.buffer_len() / recorder.native_channels().max(1);
+                    let recorded_ms = frames as u64 * 1000 / recorder.native_sample_rate().max(1) as u64;
+                    (recorder.peek_recent_rms(MONITOR_RMS_WINDOW_MS), recorder.stream_error(), recorded_ms)
+                }
+                Err(_) => return,
+            };
+
+            // 设备断开等流错误：通知前端一次（缓冲区不再增长，等待用户停止）
+            if let Some(err) = stream_error {
+                if !error_reported {
+                    error_reported = true;
+                    let _ = app.emit("recording-error", &err);
+                }
+                continue;
+            }
+
+            if max_secs > 0 && recorded_ms >= max_secs * 1000 {
+                log::warn!("录音达到时长上限 {} 秒，自动停止", max_secs);
+                auto_stop(&app, "recording-limit-reached").await;
+                return;
+            }
+
+            if silence_limit_ms == 0 {
+                continue;
+            }
+
+            if rms >= threshold {
+                speech_detected = true;
+                silence_ms = 0;
+                continue;
+            }
+            if !speech_detected {
+                continue;
+            }
+
+            silence_ms += MONITOR_INTERVAL_MS;
+            if silence_ms < silence_limit_ms {
+                continue;
+            }
+
+            log::info!("检测到 {} ms 静音，自动停止录音", silence_ms);
+            auto_stop(&app, "recording-auto-stopped").await;
+            return;
+        }
+    });
+}
+
+/// 监视任务自动停止录音：发送 event（payload 为 StopRecordingResponse），未被丢弃时继续识别
+/// （自动复制在 transcribe_audio 的完成路径中处理，与前端触发的识别一致）
+async fn auto_stop(app: &tauri::AppHandle, event: &str) {
+    let state = app.state::<AppState>();
+    match finish_recording(&state) {
+        Ok(resp) if resp.discarded => {
+            let _ = app.emit(event, &resp);
+            // 录音过短被丢弃：连续听写照常开始下一句
+            restart_if_continuous(&state, app);
+        }
+        Ok(resp) => {
+            let _ = app.emit(event, &resp);
+            if let Err(e) = crate::commands::transcribe::transcribe_audio(
+                app.state::<AppState>(),
+                app.clone(),
+            ).await {
+                log::error!("自动停止后识别失败: {}", e);
+            }
+        }
+        Err(e) => {
+            log::warn!("自动停止录音失败: {}", e);
+            restart_if_continuous(&state, app);
+        }
+    }
+}
+
+// ===== 实时音量 =====
+
+/// 音量事件最小发送间隔（毫秒），音频回调远比这频繁
+const LEVEL_EMIT_INTERVAL_MS: u64 = 50;
+
+/// 音量通道容量：接收端来不及处理时丢弃，不影响音频回调
+const LEVEL_CHANNEL_CAPACITY: usize = 64;
+
+/// 创建音量通道并启动转发任务：节流后以 event 事件发给前端
+/// （值为区间内的最大 RMS 经 meter 平滑后的结果，范围 0~1）
+/// 发送端被清除（录音停止）后发送一次 0 并结束任务
+fn spawn_level_emitter(
+    app: tauri::AppHandle,
+    event: &'static str,
+    mut meter: crate::audio::LevelMeter,
+) -> crate::audio::LevelSender {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<f32>(LEVEL_CHANNEL_CAPACITY);
+    tauri::async_runtime::spawn(async move {
+        let interval = std::time::Duration::from_millis(LEVEL_EMIT_INTERVAL_MS);
+        let mut last_emit: Option<std::time::Instant> = None;
+        let mut peak: f32 = 0.0;
+
+        while let Some(level) = rx.recv().await {
+            peak = peak.max(level);
+            let due = match last_emit {
+                Some(t) => t.elapsed() >= interval,
+                None => true,
+            };
+            if due {
+                let _ = app.emit(event, meter.update(peak));
+                last_emit = Some(std::time::Instant::now());
+                peak = 0.0;
+            }
+        }
+        // 录音结束：发送一次 0，让前端音量表归零，不停在最后一个值上
+        let _ = app.emit(event, 0.0f32);
+    });
+    tx
+}
+
+/// 按设置创建音量表平滑器
+fn level_meter(settings: &crate::state::AppSettings) -> crate::audio::LevelMeter {
+    crate::audio::LevelMeter::new(settings.level_attack, settings.level_release)
+}
+
+// ===== 预录 =====
+
+/// 按设置开启 / 关闭预录（常驻音频流 + 环形缓冲区）
+pub(crate) fn apply_pre_roll(state: &AppState, pre_roll_ms: u64) -> Result<(), String> {
+    let mut recorder = state.recorder.lock()
+        .map_err(|e| format!("录音器锁失败: {}", e))?;
+    recorder.set_pre_roll(pre_roll_ms)
+        .map_err(|e| format!("启动预录失败: {}", e))
+}
+
+/// 按设置配置首尾静音裁剪阈值
+pub(crate) fn apply_trim_silence(state: &AppState, threshold: f32) -> Result<(), String> {
+    let mut recorder = state.recorder.lock()
+        .map_err(|e| format!("录音器锁失败: {}", e))?;
+    recorder.set_trim_threshold(threshold);
+    Ok(())
+}
+
+/// 按设置配置噪声门阈值
+pub(crate) fn apply_noise_gate(state: &AppState, threshold: f32) -> Result<(), String> {
+    let mut recorder = state.recorder.lock()
+        .map_err(|e| format!("录音器锁失败: {}", e))?;
+    recorder.set_noise_gate_threshold(threshold);
+    Ok(())
+}
+
+/// 按设置切换录音使用的音频后端
+pub(crate) fn apply_audio_host(state: &AppState, host_name: Option<String>) -> Result<(), String> {
+    let mut recorder = state.recorder.lock()
+        .map_err(|e| format!("录音器锁失败: {}", e))?;
+    recorder.set_host(host_name)
+        .map_err(|e| format!("切换音频后端失败: {}", e))
+}
+
+/// 按设置切换录音使用的输入设备
+pub(crate) fn apply_input_device(state: &AppState, device_name: Option<String>) -> Result<(), String> {
+    let mut recorder = state.recorder.lock()
+        .map_err(|e| format!("录音器锁失败: {}", e))?;
+    recorder.set_input_device(device_name)
+        .map_err(|e| format!("切换输入设备失败: {}", e))
+}
+
+// ===== 连续听写 =====
+
+/// 开启 / 关闭连续听写
+///
+/// 开启后每句录音结束都会自动重新开始录音，每句话各自成为一条历史记录；
+/// 识别失败、结果为空或录音过短被丢弃时同样重启，下一句照常继续。
+/// 用户取消识别或自动重启录音失败时退出连续模式，并发送 continuous-dictation-changed(false)；
+/// 关闭后当前这句（若正在录音）照常停止、识别，之后不再自动重启
+#[tauri::command]
+pub async fn set_continuous_dictation(
+    enabled: bool,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let should_start = {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.continuous_active = enabled;
+        enabled && inner.recording_status == RecordingStatus::Idle
+    };
+
+    if should_start {
+        if let Err(e) = begin_recording(&state, &app) {
+            if let Ok(mut inner) = state.inner.lock() {
+                inner.continuous_active = false;
+            }
+            return Err(e);
+        }
+    }
+
+    let _ = app.emit("continuous-dictation-changed", enabled);
+    log::info!("连续听写已{}", if enabled { "开启" } else { "关闭" });
+    Ok(())
+}
+
+/// 一句录音处理结束（识别成功 / 失败、录音被丢弃）后调用：连续听写开启时自动重新开始录音
+///
+/// 调用前状态已置回 Idle，不会与 Processing→Idle 的切换竞争
+pub(crate) fn restart_if_continuous(state: &AppState, app: &tauri::AppHandle) {
+    let active = state.inner.lock()
+        .map(|inner| inner.continuous_active)
+        .unwrap_or(false);
+    if !active {
+        return;
+    }
+
+    match begin_recording(state, app) {
+        Ok(()) => {
+            log::info!("连续听写：已自动开始下一句录音");
+            let _ = app.emit("recording-restarted", ());
+        }
+        Err(e) => {
+            log::warn!("连续听写：自动重启录音失败，退出连续模式: {}", e);
+            stop_continuous(state, app);
+        }
+    }
+}
+
+/// 退出连续听写并通知前端（已关闭时不重复发送事件）
+pub(crate) fn stop_continuous(state: &AppState, app: &tauri::AppHandle) {
+    let was_active = state.inner.lock()
+        .map(|mut inner| std::mem::replace(&mut inner.continuous_active, false))
+        .unwrap_or(false);
+    if was_active {
+        let _ = app.emit("continuous-dictation-changed", false);
+    }
+}
+
+// ===== 录音导出 =====
+
+/// 把录音保存为 16kHz 单声道 WAV 文件，返回写入的字节数
+///
+/// path: 目标文件路径（必须以 .wav 结尾，所在目录需存在）
+/// id:   历史记录 ID（只保留最近一次识别的录音，其他记录返回错误）；
+///       为空时导出当前待识别的录音，没有则导出最近一次识别的录音
+#[tauri::command]
+pub async fn save_recording_wav(
+    path: String,
+    id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let path = std::path::PathBuf::from(path.trim());
+    let is_wav = path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("wav"))
+        .unwrap_or(false);
+    if !is_wav {
+        return Err("导出路径必须以 .wav 结尾".to_string());
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if !parent.is_dir() {
+            return Err(format!("目录不存在: {:?}", parent));
+        }
+    }
+
+    let wav = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        let samples = match id {
+            Some(id) => recording_for_item(&inner, &id)?,
+            None => inner.audio_buffer.as_ref()
+                .or(inner.last_recording.as_ref())
+                .ok_or_else(|| "没有可导出的录音".to_string())?,
+        };
+        crate::cloud::encode_wav(samples, 16000, 1)
+    };
+
+    std::fs::write(&path, &wav)
+        .map_err(|e| format!("写入 WAV 文件失败: {}", e))?;
+
+    log::info!("录音已导出: {:?}（{} 字节）", path, wav.len());
+    Ok(wav.len())
+}
+
+/// 外部读取录音的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingPcmFile {
+    /// 临时文件路径（由调用方负责读取后删除）
+    pub path: String,
+    /// "wav"（16-bit PCM WAV）或 "f32"（裸 f32 小端序）
+    pub format: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_count: usize,
+    /// 对应的历史记录 ID（尚未识别的录音为 None）
+    pub item_id: Option<String>,
+}
+
+/// 把录音写入临时文件，供外部工具（自定义 VAD / 其他 ASR 等）读取
+///
+/// format: "wav"（默认）或 "f32"（16kHz 单声道裸 f32 小端序，无损）
+/// 取录音的规则与 save_recording_wav 相同：优先当前待识别的录音，
+/// 否则为最近一次识别的录音；已被清空的缓冲区不会返回旧数据
+#[tauri::command]
+pub async fn get_last_recording_pcm(
+    format: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<RecordingPcmFile, String> {
+    let format = format.unwrap_or_else(|| "wav".to_string()).to_lowercase();
+    if format != "wav" && format != "f32" {
+        return Err(format!("不支持的格式: {}（可选 wav / f32）", format));
+    }
+
+    let (bytes, sample_count, item_id) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        let (samples, item_id) = match (&inner.audio_buffer, &inner.last_recording) {
+            (Some(pending), _) => (pending, None),
+            (None, Some(last)) => (last, inner.last_recording_id.clone()),
+            (None, None) => return Err("没有可读取的录音".to_string()),
+        };
+        let bytes = if format == "wav" {
+            crate::cloud::encode_wav(samples, 16000, 1)
+        } else {
+            samples.iter().flat_map(|s| s.to_le_bytes()).collect()
+        };
+        (bytes, samples.len(), item_id)
+    };
+
+    let file = tempfile::Builder::new()
+        .prefix("voxie-recording-")
+        .suffix(&format!(".{}", format))
+        .tempfile()
+        .map_err(|e| format!("创建临时文件失败: {}", e))?;
+    std::fs::write(file.path(), &bytes)
+        .map_err(|e| format!("写入临时文件失败: {}", e))?;
+    let (_, path) = file.keep()
+        .map_err(|e| format!("保存临时文件失败: {}", e))?;
+
+    log::info!("录音已写入临时文件: {:?}（{} 字节）", path, bytes.len());
+    Ok(RecordingPcmFile {
+        path: path.to_string_lossy().to_string(),
+        format,
+        sample_rate: 16000,
+        channels: 1,
+        sample_count,
+        item_id,
+    })
+}
+
+/// 取历史记录对应的录音：只有最近一次识别的录音保留在内存中
+pub(crate) fn recording_for_item<'a>(inner: &'a InnerState, id: &str) -> Result<&'a Vec<f32>, String> {
+    match (&inner.last_recording, &inner.last_recording_id) {
+        (Some(samples), Some(last_id)) if last_id == id => Ok(samples),
+        _ => Err(format!("历史记录 {} 未保存音频，只能导出最近一次识别的录音", id)),
+    }
+}
+
+// ===== 设备能力 =====
+
+/// 查询当前输入设备支持的采样格式 / 声道数 / 采样率范围
+#[tauri::command]
+pub async fn get_device_capabilities(
+    state: State<'_, AppState>,
+) -> Result<crate::audio::DeviceCapabilities, String> {
+    let (host_name, device_name) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        (inner.settings.audio_host.clone(), inner.settings.input_device.clone())
+    };
+    crate::audio::probe_input_device(host_name.as_deref(), device_name.as_deref())
+        .map_err(|e| format!("查询设备能力失败: {}", e))
+}
+
+/// 列出当前音频后端下的输入设备（麦克风），供设置中选择 input_device
+#[tauri::command]
+pub async fn list_input_devices(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::audio::InputDeviceInfo>, String> {
+    let host_name = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.settings.audio_host.clone()
+    };
+    crate::audio::list_input_devices(host_name.as_deref())
+        .map_err(|e| format!("枚举输入设备失败: {}", e))
+}
+
+/// 列出可用的音频后端（Windows 上如 WASAPI / ASIO）
+#[tauri::command]
+pub async fn list_audio_hosts() -> Result<Vec<crate::audio::AudioHostInfo>, String> {
+    Ok(crate::audio::list_hosts())
+}
+
+// ===== 麦克风测试 =====
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MicTestResult {
+    pub device_name: String,
+    /// 设备原生采样率（Hz）
+    pub sample_rate: u32,
+    /// 设备原生声道数
+    pub channels: usize,
+    /// 重采样到 16kHz 后的 RMS 音量
+    pub rms: f32,
+    /// 峰值（绝对值）
+    pub peak: f32,
+    pub duration_ms: u64,
+    /// 音量评估："silent" / "low" / "good" / "clipping"
+    pub level: String,
+}
+
+/// 测试麦克风：录制一小段音频并返回音量等信息
+///
+/// 使用临时的 AudioRecorder 实例，不影响正在进行的录音，
+/// 也不写入历史记录、不触发识别
+///
+/// 测试期间通过 mic-test-level 事件实时报告音量
+#[tauri::command]
+pub async fn test_microphone(
+    duration_ms: u64,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<MicTestResult, String> {
+    let duration_ms = duration_ms.clamp(200, 10_000);
+    let (host_name, device_name, meter) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        (inner.settings.audio_host.clone(), inner.settings.input_device.clone(), level_meter(&inner.settings))
+    };
+
+    let mut recorder = crate::audio::AudioRecorder::new();
+    recorder.set_host(host_name)
+        .map_err(|e| format!("切换音频后端失败: {}", e))?;
+    recorder.set_input_device(device_name)
+        .map_err(|e| format!("切换输入设备失败: {}", e))?;
+    recorder.start()
+        .map_err(|e| format!("启动录音失败: {}", e))?;
+    recorder.set_level_sender(Some(spawn_level_emitter(app, "mic-test-level", meter)));
+
+    tokio::time::sleep(std::time::Duration::from_millis(duration_ms)).await;
+
+    let samples = recorder.stop();
+    let rms = crate::whisper::audio_rms(&samples);
+    let peak = samples.iter().fold(0.0f32, |m, &s| m.max(s.abs()));
+
+    let level = if rms < 0.001 {
+        "silent"
+    } else if peak >= 0.99 {
+        "clipping"
+    } else if rms < 0.01 {
+        "low"
+    } else {
+        "good"
+    };
+
+    log::info!(
+        "麦克风测试: {} ({}Hz {}ch), RMS={:.6}, 峰值={:.4}, 评估={}",
+        recorder.device_name(), recorder.native_sample_rate(), recorder.native_channels(),
+        rms, peak, level
+    );
+
+    Ok(MicTestResult {
+        device_name: recorder.device_name().to_string(),
+        sample_rate: recorder.native_sample_rate(),
+        channels: recorder.native_channels(),
+        rms,
+        peak,
+        duration_ms: crate::audio::samples_to_ms(samples.len(), 16000),
+        level: level.to_string(),
+    })
+}
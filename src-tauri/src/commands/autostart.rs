@@ -0,0 +1,66 @@
+// commands/autostart.rs - 开机自启动命令
+// 基于 tauri-plugin-autostart：macOS 使用 LaunchAgent，Windows 写注册表启动项
+
+use tauri::{AppHandle, State};
+use tauri_plugin_autostart::ManagerExt;
+use crate::state::AppState;
+
+/// 开启开机自启动
+#[tauri::command]
+pub async fn enable_autostart(
+    app:   AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    set_autostart(&app, &state, true)
+}
+
+/// 关闭开机自启动
+#[tauri::command]
+pub async fn disable_autostart(
+    app:   AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    set_autostart(&app, &state, false)
+}
+
+/// 查询系统中是否已注册开机自启动
+#[tauri::command]
+pub async fn is_autostart_enabled(app: AppHandle) -> Result<bool, String> {
+    app.autolaunch()
+        .is_enabled()
+        .map_err(|e| format!("查询开机自启动状态失败: {}", e))
+}
+
+/// 更新系统登录项注册，并同步 settings.autostart 到内存和磁盘
+fn set_autostart(app: &AppHandle, state: &AppState, enabled: bool) -> Result<(), String> {
+    apply_autostart(app, enabled)?;
+
+    let settings = {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        inner.settings.autostart = enabled;
+        inner.settings.clone()
+    };
+    crate::commands::settings::persist_settings(app, &settings)
+}
+
+/// 让系统登录项注册与 enabled 一致（已一致则不做任何事）
+/// save_settings 保存时也会调用，确保设置页的开关真正生效
+pub(crate) fn apply_autostart(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    let current = autolaunch.is_enabled().unwrap_or(false);
+    if current == enabled {
+        return Ok(());
+    }
+
+    if enabled {
+        autolaunch.enable()
+            .map_err(|e| format!("开启开机自启动失败: {}", e))?;
+    } else {
+        autolaunch.disable()
+            .map_err(|e| format!("关闭开机自启动失败: {}", e))?;
+    }
+
+    log::info!("开机自启动已{}", if enabled { "开启" } else { "关闭" });
+    Ok(())
+}
@@ -1,19 +1,86 @@
 // commands/clipboard.rs - 剪贴板操作命令
 
+use tauri::State;
 use tauri_plugin_clipboard_manager::ClipboardExt;
+use crate::state::AppState;
 
 /// 将文本复制到剪贴板
 /// 前端调用：invoke('copy_to_clipboard', { text: '...' })
+///
+/// 开启 restore_clipboard 时，先记下剪贴板原有文本，
+/// 延迟 clipboard_restore_delay_ms 后恢复（期间剪贴板被用户改动则不恢复）
 #[tauri::command]
 pub async fn copy_to_clipboard(
     text: String,
     app: tauri::AppHandle,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
+    write_clipboard(&app, &state, text)
+}
+
+/// 写入剪贴板（按设置恢复原有内容），供 copy_to_clipboard 命令和识别完成后的自动复制共用
+pub(crate) fn write_clipboard(app: &tauri::AppHandle, state: &AppState, text: String) -> Result<(), String> {
+    let (restore, delay_ms) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        (inner.settings.restore_clipboard, inner.settings.clipboard_restore_delay_ms)
+    };
+
+    // 写入前先保存原有内容（读取失败 / 非文本内容则不恢复）
+    let previous = if restore {
+        app.clipboard().read_text().ok()
+    } else {
+        None
+    };
+
     // 使用 tauri-plugin-clipboard-manager 插件
     app.clipboard()
         .write_text(text.clone())
         .map_err(|e| format!("复制到剪贴板失败: {}", e))?;
 
     log::info!("已复制到剪贴板: {} 个字符", text.len());
+
+    if let Some(previous) = previous {
+        if previous != text {
+            schedule_clipboard_restore(app.clone(), text, previous, delay_ms);
+        }
+    }
     Ok(())
 }
+
+/// 复制最近一条识别结果（history 第一条）到剪贴板，返回复制的文本
+/// 悬浮窗已关闭或需要再次粘贴时使用，可绑定快捷键
+#[tauri::command]
+pub async fn copy_last_result(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let text = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.history.first()
+            .map(|item| item.text.clone())
+            .ok_or_else(|| "暂无识别记录".to_string())?
+    };
+
+    copy_to_clipboard(text.clone(), app, state).await?;
+    Ok(text)
+}
+
+/// 延迟恢复剪贴板
+/// 只有剪贴板仍是我们写入的内容时才恢复，避免覆盖用户在此期间新复制的内容
+fn schedule_clipboard_restore(app: tauri::AppHandle, written: String, previous: String, delay_ms: u64) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+
+        let current = app.clipboard().read_text().unwrap_or_default();
+        if current != written {
+            log::info!("剪贴板已被改动，跳过恢复");
+            return;
+        }
+        match app.clipboard().write_text(previous) {
+            Ok(()) => log::info!("已恢复剪贴板原有内容"),
+            Err(e) => log::warn!("恢复剪贴板失败: {}", e),
+        }
+    });
+}
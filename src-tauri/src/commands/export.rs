@@ -0,0 +1,225 @@
+// commands/export.rs - 导出历史记录
+//   export_sample       识别样本（音频 + 文本），用于整理训练数据集
+//   export_history_item 单条记录另存为 txt / srt 字幕 / json
+
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::state::{AppState, HistoryItem};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSample {
+    pub wav_path: String,
+    pub text_path: String,
+}
+
+/// 把一条历史记录导出为 whisper 风格的训练样本：<id>.wav + <id>.txt
+///
+/// dir: 目标目录（需已存在）
+/// 音频只保留最近一次识别的录音，其他记录返回错误
+#[tauri::command]
+pub async fn export_sample(
+    id: String,
+    dir: String,
+    state: State<'_, AppState>,
+) -> Result<ExportedSample, String> {
+    let dir = PathBuf::from(dir.trim());
+    if !dir.is_dir() {
+        return Err(format!("目录不存在: {:?}", dir));
+    }
+
+    let (wav, text) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        let item = inner.history.iter()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("未找到 ID 为 {} 的记录", id))?;
+        let samples = crate::commands::audio::recording_for_item(&inner, &id)?;
+        (crate::cloud::encode_wav(samples, 16000, 1), item.text.clone())
+    };
+
+    let wav_path = dir.join(format!("{}.wav", id));
+    let text_path = dir.join(format!("{}.txt", id));
+
+    std::fs::write(&wav_path, &wav)
+        .map_err(|e| format!("写入 WAV 文件失败: {}", e))?;
+    std::fs::write(&text_path, text.as_bytes())
+        .map_err(|e| format!("写入文本文件失败: {}", e))?;
+
+    log::info!("已导出样本: {:?} / {:?}", wav_path, text_path);
+    Ok(ExportedSample {
+        wav_path: wav_path.to_string_lossy().to_string(),
+        text_path: text_path.to_string_lossy().to_string(),
+    })
+}
+
+/// 单条历史记录的导出格式
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// 纯文本（识别结果）
+    Txt,
+    /// SRT 字幕（需要段落时间戳，没有时整段作为一条字幕）
+    Srt,
+    /// 完整记录（含元数据和段落）
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Txt => "txt",
+            ExportFormat::Srt => "srt",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// 弹出保存对话框，把一条历史记录导出为 txt / srt / json
+///
+/// 返回写入的文件路径；用户取消对话框时返回 None
+/// SRT 使用带时间戳识别（transcribe_audio_detailed）保存的段落
+#[tauri::command]
+pub async fn export_history_item(
+    id: String,
+    format: ExportFormat,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<Option<String>, String> {
+    let item = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.history.iter()
+            .find(|item| item.id == id)
+            .cloned()
+            .ok_or_else(|| format!("未找到 ID 为 {} 的记录", id))?
+    };
+
+    let content = match format {
+        ExportFormat::Txt => item.text.clone(),
+        ExportFormat::Srt => to_srt(&item),
+        ExportFormat::Json => serde_json::to_string_pretty(&item)
+            .map_err(|e| format!("序列化记录失败: {}", e))?,
+    };
+
+    // 对话框会阻塞到用户选择完成，放到阻塞线程中等待
+    let ext = format.extension();
+    let dialog = app.dialog().file()
+        .set_file_name(format!("voxie-{}.{}", item.timestamp.format("%Y%m%d-%H%M%S"), ext))
+        .add_filter(ext.to_uppercase(), &[ext]);
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file())
+        .await
+        .map_err(|e| format!("保存对话框异常: {}", e))?;
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let path = picked.into_path()
+        .map_err(|e| format!("保存路径无效: {}", e))?;
+
+    std::fs::write(&path, content.as_bytes())
+        .map_err(|e| format!("写入文件失败: {}", e))?;
+
+    log::info!("已导出记录 {} 为 {}: {:?}", id, ext, path);
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// 生成 SRT 字幕；没有段落时间戳时整段文本作为一条字幕（覆盖整个录音时长）
+fn to_srt(item: &HistoryItem) -> String {
+    let cues: Vec<(i64, i64, &str)> = match &item.segments {
+        Some(segments) if !segments.is_empty() => segments.iter()
+            .map(|s| (s.start_ms, s.end_ms, s.text.trim()))
+            .filter(|(_, _, text)| !text.is_empty())
+            .collect(),
+        _ => vec![(0, item.duration_ms as i64, item.text.trim())],
+    };
+
+    let mut srt = String::new();
+    for (index, (start, end, text)) in cues.into_iter().enumerate() {
+        srt.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            srt_timestamp(start),
+            srt_timestamp(end),
+            text,
+        ));
+    }
+    srt
+}
+
+/// 毫秒 → SRT 时间戳（HH:MM:SS,mmm）
+fn srt_timestamp(ms: i64) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::TranscriptionMode;
+    use crate::whisper::Segment;
+
+    fn item(text: &str, duration_ms: u64, segments: Option<Vec<Segment>>) -> HistoryItem {
+        HistoryItem {
+            id: "1".to_string(),
+            text: text.to_string(),
+            timestamp: chrono::Utc::now(),
+            duration_ms,
+            mode: TranscriptionMode::Local,
+            model_name: None,
+            language: None,
+            is_fallback: false,
+            translation: None,
+            segments,
+            is_translation: false,
+            pinned: false,
+        }
+    }
+
+    fn segment(text: &str, start_ms: i64, end_ms: i64) -> Segment {
+        Segment { text: text.to_string(), start_ms, end_ms }
+    }
+
+    #[test]
+    fn srt_timestamp_uses_comma_milliseconds() {
+        assert_eq!(srt_timestamp(0), "00:00:00,000");
+        assert_eq!(srt_timestamp(1_005), "00:00:01,005");
+        assert_eq!(srt_timestamp(61_234), "00:01:01,234");
+        assert_eq!(srt_timestamp(-50), "00:00:00,000");
+    }
+
+    #[test]
+    fn srt_timestamp_rolls_over_hours() {
+        assert_eq!(srt_timestamp(3_599_999), "00:59:59,999");
+        assert_eq!(srt_timestamp(3_600_000), "01:00:00,000");
+        assert_eq!(srt_timestamp(36_061_001), "10:01:01,001");
+    }
+
+    #[test]
+    fn to_srt_numbers_segments_and_skips_blank_ones() {
+        let srt = to_srt(&item("你好 世界", 3000, Some(vec![
+            segment(" 你好", 0, 1200),
+            segment("  ", 1200, 1500),
+            segment("世界 ", 1500, 3000),
+        ])));
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:01,200\n你好\n\n\
+             2\n00:00:01,500 --> 00:00:03,000\n世界\n\n"
+        );
+    }
+
+    #[test]
+    fn to_srt_falls_back_to_whole_text_without_segments() {
+        let expected = "1\n00:00:00,000 --> 00:00:02,500\n整段文本\n\n";
+        assert_eq!(to_srt(&item(" 整段文本 ", 2500, None)), expected);
+        assert_eq!(to_srt(&item(" 整段文本 ", 2500, Some(Vec::new()))), expected);
+    }
+}
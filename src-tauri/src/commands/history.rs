@@ -1,7 +1,21 @@
 // commands/history.rs - 历史记录管理命令
 
-use tauri::State;
-use crate::state::{AppState, HistoryItem};
+use tauri::{AppHandle, Manager, State};
+use once_cell::sync::Lazy;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use crate::state::{AppState, HistoryItem, TranscriptionMode};
+use crate::commands::translate::{detect_source_lang, translate};
+
+/// 历史记录文件（app 数据目录下）
+pub(crate) const HISTORY_FILE: &str = "voxie-history.json";
+
+/// 写入中的临时文件后缀：先写临时文件再 rename 覆盖，崩溃时旧文件保持完整
+const HISTORY_TMP_SUFFIX: &str = ".tmp";
+
+/// 串行化历史写盘：快照在锁内获取，保证后写入的总是较新的快照
+static HISTORY_WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
 /// 获取历史记录列表
 #[tauri::command]
@@ -14,37 +28,316 @@ pub async fn get_history(
     Ok(inner.history.clone())
 }
 
-/// 清空所有历史记录
+/// 搜索历史记录：文本包含 query（不区分大小写），可按识别模式过滤
+/// query 为空时只按模式过滤；结果保持最新在前
+#[tauri::command]
+pub async fn search_history(
+    query: String,
+    mode: Option<TranscriptionMode>,
+    state: State<'_, AppState>,
+) -> Result<Vec<HistoryItem>, String> {
+    let needle = query.trim().to_lowercase();
+
+    let inner = state.inner.lock()
+        .map_err(|e| format!("获取状态锁失败: {}", e))?;
+    Ok(inner.history.iter()
+        .filter(|item| match &mode {
+            Some(m) => &item.mode == m,
+            None => true,
+        })
+        .filter(|item| needle.is_empty() || item.text.to_lowercase().contains(&needle))
+        .cloned()
+        .collect())
+}
+
+/// 清空历史记录（置顶的记录保留，需要时单条删除）
 #[tauri::command]
 pub async fn clear_history(
+    app:   AppHandle,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let mut inner = state.inner.lock()
-        .map_err(|e| format!("获取状态锁失败: {}", e))?;
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        inner.history.retain(|item| item.pinned);
+    }
 
-    inner.history.clear();
-    log::info!("历史记录已清空");
+    persist_history(&app, &state)?;
+    log::info!("历史记录已清空（保留置顶）");
     Ok(())
 }
 
+/// 清除早于 days 天前的历史记录，保留较新的记录和置顶的记录
+/// 返回删除的条数
+#[tauri::command]
+pub async fn clear_history_before(
+    days: u32,
+    app:   AppHandle,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
+
+    let removed = {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        let before = inner.history.len();
+        inner.history.retain(|item| item.pinned || item.timestamp >= cutoff);
+        before - inner.history.len()
+    };
+
+    if removed > 0 {
+        persist_history(&app, &state)?;
+    }
+    log::info!("已清除 {} 天前的历史记录: {} 条", days, removed);
+    Ok(removed)
+}
+
 /// 删除单条历史记录
 /// id: 要删除的记录的 ID
 #[tauri::command]
 pub async fn delete_history_item(
     id: String,
+    app:   AppHandle,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let mut inner = state.inner.lock()
-        .map_err(|e| format!("获取状态锁失败: {}", e))?;
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
 
-    let before = inner.history.len();
-    inner.history.retain(|item| item.id != id);
-    let after = inner.history.len();
+        let before = inner.history.len();
+        inner.history.retain(|item| item.id != id);
+        let after = inner.history.len();
 
-    if before == after {
-        return Err(format!("未找到 ID 为 {} 的记录", id));
+        if before == after {
+            return Err(format!("未找到 ID 为 {} 的记录", id));
+        }
     }
 
+    persist_history(&app, &state)?;
     log::info!("已删除历史记录: {}", id);
     Ok(())
 }
+
+/// 修改单条历史记录的文本（修正识别错误）
+/// id: 记录 ID；text: 修改后的文本
+///
+/// 之后复制、导出都使用修改后的文本；原有的翻译和段落时间戳与新文本不再对应，一并清除
+#[tauri::command]
+pub async fn update_history_item(
+    id: String,
+    text: String,
+    app:   AppHandle,
+    state: State<'_, AppState>,
+) -> Result<HistoryItem, String> {
+    let item = {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        let item = inner.history.iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("未找到 ID 为 {} 的记录", id))?;
+        if item.text == text {
+            return Ok(item.clone());
+        }
+        item.text = text;
+        item.translation = None;
+        item.segments = None;
+        item.clone()
+    };
+
+    persist_history(&app, &state)?;
+    log::info!("已修改历史记录: {}", id);
+    Ok(item)
+}
+
+/// 固定 / 取消固定单条历史记录，返回新的固定状态
+/// 固定的记录不受 max_history 限制，不会因新记录增加而被删除
+#[tauri::command]
+pub async fn toggle_history_pin(
+    id: String,
+    app:   AppHandle,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let pinned = {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        let item = inner.history.iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("未找到 ID 为 {} 的记录", id))?;
+        item.pinned = !item.pinned;
+        item.pinned
+    };
+
+    persist_history(&app, &state)?;
+    log::info!("历史记录 {} {}", id, if pinned { "已固定" } else { "已取消固定" });
+    Ok(pinned)
+}
+
+/// 把历史记录限制在 max 条以内：固定的记录全部保留，从最旧的未固定记录开始删除
+/// 保持原有的最新在前顺序；固定记录本身超过 max 时只保留固定记录
+pub(crate) fn truncate_history(history: &mut Vec<HistoryItem>, max: usize) {
+    if history.len() <= max {
+        return;
+    }
+    let pinned = history.iter().filter(|item| item.pinned).count();
+    let mut unpinned_kept = 0;
+    history.retain(|item| {
+        if item.pinned {
+            return true;
+        }
+        unpinned_kept += 1;
+        unpinned_kept + pinned <= max
+    });
+}
+
+/// 翻译单条历史记录
+/// id: 记录 ID；to: 目标语言（"zh-hans" | "zh-hant" | "en"）
+///
+/// 源语言依据记录的识别语言和文本内容推断，翻译结果写回该记录
+#[tauri::command]
+pub async fn translate_history_item(
+    id: String,
+    to: String,
+    app:   AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let (text, language) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        let item = inner.history.iter()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("未找到 ID 为 {} 的记录", id))?;
+        (item.text.clone(), item.language.clone())
+    };
+
+    let from = detect_source_lang(&text, language.as_deref());
+    let translated = translate(&state, &app, text, from, &to).await?;
+
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        // 翻译期间记录可能已被删除，此时只返回结果
+        if let Some(item) = inner.history.iter_mut().find(|item| item.id == id) {
+            item.translation = Some(translated.clone());
+        }
+    }
+    persist_history(&app, &state)?;
+
+    log::info!("已翻译历史记录: {} ({} → {})", id, from, to);
+    Ok(translated)
+}
+
+/// 历史记录文件路径
+pub(crate) fn history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("获取数据目录失败: {}", e))?;
+    Ok(dir.join(HISTORY_FILE))
+}
+
+/// 把内存中的历史记录写入磁盘（每次增删改后调用）
+///
+/// 原子写入：先完整写入临时文件并 fsync，再 rename 覆盖正式文件。
+/// 进程在任何时刻崩溃，正式文件要么是旧版本要么是新版本，不会出现写了一半的文件
+pub(crate) fn persist_history(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let path = history_path(app)?;
+    let _guard = HISTORY_WRITE_LOCK.lock()
+        .map_err(|e| format!("历史写盘锁失败: {}", e))?;
+
+    let json = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        serde_json::to_vec(&inner.history)
+            .map_err(|e| format!("序列化历史记录失败: {}", e))?
+    };
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("创建数据目录失败: {}", e))?;
+    }
+    let tmp = tmp_path(&path);
+    let mut file = std::fs::File::create(&tmp)
+        .map_err(|e| format!("写入历史记录失败: {}", e))?;
+    file.write_all(&json)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("写入历史记录失败: {}", e))?;
+    drop(file);
+    std::fs::rename(&tmp, &path)
+        .map_err(|e| format!("保存历史记录失败: {}", e))?;
+    Ok(())
+}
+
+/// 应用启动时从磁盘加载历史记录，由 lib.rs setup() 在加载设置之后调用
+///
+/// 只读取正式文件；残留的临时文件说明上次写入中途崩溃，内容不完整，直接删除
+pub fn load_persisted_history(app: &AppHandle, state: &AppState) {
+    let path = match history_path(app) {
+        Ok(p) => p,
+        Err(e) => {
+            log::warn!("{}", e);
+            return;
+        }
+    };
+
+    let Some(mut history) = read_history_file(&path) else {
+        return;
+    };
+    let mut inner = state.inner.lock().unwrap();
+    truncate_history(&mut history, inner.settings.max_history);
+    log::info!("已从磁盘加载历史记录: {} 条", history.len());
+    inner.history = history;
+}
+
+/// 读取历史记录文件：先删除残留的临时文件，文件不存在或格式无效时返回 None
+fn read_history_file(path: &std::path::Path) -> Option<Vec<HistoryItem>> {
+    let tmp = tmp_path(path);
+    if tmp.exists() {
+        log::warn!("发现未完成的历史写入，已忽略: {:?}", tmp);
+        let _ = std::fs::remove_file(&tmp);
+    }
+
+    let bytes = match std::fs::read(path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            log::warn!("读取历史记录失败: {}", e);
+            return None;
+        }
+    };
+
+    match serde_json::from_slice::<Vec<HistoryItem>>(&bytes) {
+        Ok(history) => Some(history),
+        Err(e) => {
+            log::warn!("历史记录文件格式无效，已忽略（{}）", e);
+            None
+        }
+    }
+}
+
+fn tmp_path(path: &std::path::Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(HISTORY_TMP_SUFFIX);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leftover_tmp_file_is_ignored_and_removed_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        std::fs::write(
+            &path,
+            r#"[{"id":"a1","text":"你好世界","timestamp":"2026-01-01T00:00:00Z","durationMs":1200,"mode":"local"}]"#,
+        ).unwrap();
+        // 模拟上次写入中途崩溃留下的半截临时文件
+        let tmp = tmp_path(&path);
+        std::fs::write(&tmp, r#"[{"id":"b2","text":"写了一半"#).unwrap();
+
+        let history = read_history_file(&path).expect("应读取到正式文件内容");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, "a1");
+        assert_eq!(history[0].text, "你好世界");
+        assert!(!tmp.exists(), "残留的临时文件应被删除");
+    }
+}
@@ -9,3 +9,8 @@ pub mod settings;
 pub mod history;
 pub mod clipboard;
 pub mod window;
+pub mod autostart;
+pub mod export;
+pub mod diagnostics;
+pub mod storage;
+pub mod shortcut;
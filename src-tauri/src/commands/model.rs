@@ -2,8 +2,23 @@
 
 use tauri::{State, Emitter};
 use serde::Serialize;
-use crate::state::{AppState, ModelStatus};
-use crate::whisper::{WhisperModel, get_model_path, is_model_downloaded};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use crate::state::{AppState, ModelStatus, DownloadState};
+use crate::whisper::{WhisperModel, get_model_path, get_models_dir, is_model_downloaded};
+
+/// 模型加载线程栈大小：32MB
+/// 避免 Windows 默认 1MB 栈溢出
+const LOAD_STACK_SIZE: usize = 32 * 1024 * 1024;
+
+/// 模型加载心跳间隔（毫秒），让前端区分"慢但在跑"和"卡死"
+const LOAD_HEARTBEAT_MS: u64 = 1000;
+
+/// 下载前磁盘空间的安全余量（MB）
+const DISK_SPACE_MARGIN_MB: u64 = 100;
+
+/// 模型下载的连接超时（秒）：超时后换下一个下载站点
+const DOWNLOAD_CONNECT_TIMEOUT_SECS: u64 = 15;
 
 /// 模型信息
 #[derive(Debug, Serialize)]
@@ -13,19 +28,37 @@ pub struct ModelInfo {
     pub display_name: String,   // 显示名称
     pub is_downloaded: bool,    // 是否已下载
     pub file_size_mb: f64,      // 文件大小（MB）
+    pub required_ram_mb: u64,   // 加载后预估占用内存（MB）
+    pub may_exceed_ram: bool,   // 可能超出本机当前可用内存
+    pub english_only: bool,     // 仅英语的 .en 模型
 }
 
 /// 获取模型状态
+///
+/// model_name 为空时返回所有模型的下载状态；指定时只返回该模型
 #[tauri::command]
 pub async fn get_model_status(
+    model_name: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<ModelStatusResponse, String> {
     let inner = state.inner.lock()
         .map_err(|e| format!("获取状态锁失败: {}", e))?;
 
+    let downloads = match model_name {
+        Some(name) => {
+            let model = WhisperModel::from_str(&name)
+                .ok_or_else(|| format!("未知的模型名称: {}", name))?;
+            inner.downloads
+                .get(model.name())
+                .map(|d| HashMap::from([(model.name().to_string(), d.clone())]))
+                .unwrap_or_default()
+        }
+        None => inner.downloads.clone(),
+    };
+
     Ok(ModelStatusResponse {
         status: inner.model_status.clone(),
-        download_progress: inner.download_progress,
+        downloads,
         current_model: inner.settings.local_model.clone(),
     })
 }
@@ -34,20 +67,17 @@ pub async fn get_model_status(
 #[serde(rename_all = "camelCase")]
 pub struct ModelStatusResponse {
     pub status: ModelStatus,
-    pub download_progress: f64,
+    /// 各模型的下载状态（key 为模型名）
+    pub downloads: HashMap<String, DownloadState>,
     pub current_model: String,
 }
 
 /// 列出所有模型及其下载状态
 #[tauri::command]
 pub async fn list_models() -> Result<Vec<ModelInfo>, String> {
-    let models = vec![
-        WhisperModel::Tiny,
-        WhisperModel::Base,
-        WhisperModel::Small,
-        WhisperModel::Medium,
-        WhisperModel::LargeV3,
-    ];
+    let models = WhisperModel::all();
+
+    let available_ram_mb = crate::whisper::available_memory_mb();
 
     let mut result = Vec::new();
     for model in models {
@@ -61,28 +91,18 @@ pub async fn list_models() -> Result<Vec<ModelInfo>, String> {
                 .unwrap_or(0.0)
         } else {
             // 预估大小
-            match model {
-                WhisperModel::Tiny => 39.0,
-                WhisperModel::Base => 74.0,
-                WhisperModel::Small => 244.0,
-                WhisperModel::Medium => 769.0,
-                WhisperModel::LargeV3 => 1550.0,
-            }
-        };
-
-        let name = match model {
-            WhisperModel::Tiny => "tiny",
-            WhisperModel::Base => "base",
-            WhisperModel::Small => "small",
-            WhisperModel::Medium => "medium",
-            WhisperModel::LargeV3 => "large-v3",
+            model.approx_size_mb()
         };
 
         result.push(ModelInfo {
-            name: name.to_string(),
+            name: model.name().to_string(),
             display_name: model.display_name().to_string(),
             is_downloaded,
             file_size_mb,
+            required_ram_mb: model.required_ram_mb(),
+            // 可用内存未知（0）时不标记
+            may_exceed_ram: available_ram_mb > 0 && model.required_ram_mb() > available_ram_mb,
+            english_only: model.is_english_only(),
         });
     }
 
@@ -91,6 +111,8 @@ pub async fn list_models() -> Result<Vec<ModelInfo>, String> {
 
 /// 下载模型命令
 /// 使用 Tauri 的事件系统报告下载进度
+///
+/// 每个模型的进度独立记录在 inner.downloads 中，多个模型可同时下载
 #[tauri::command]
 pub async fn download_model(
     model_name: String,
@@ -99,58 +121,255 @@ pub async fn download_model(
 ) -> Result<(), String> {
     let model = WhisperModel::from_str(&model_name)
         .ok_or_else(|| format!("未知的模型名称: {}", model_name))?;
+    let name = model.name().to_string();
 
     // 检查是否已下载
     if is_model_downloaded(&model) {
         return Ok(());
     }
 
-    // 更新状态为"下载中"
+    // 磁盘空间预检：空间不足时直接报错，避免下载到一半写入失败
+    check_disk_space(&model)?;
+
+    // 登记为"下载中"（同一模型不允许重复下载）
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        if let Some(DownloadState { status: ModelStatus::Downloading, .. }) = inner.downloads.get(&name) {
+            return Err(format!("模型 {} 正在下载中", model.display_name()));
+        }
+        inner.downloads.insert(name.clone(), DownloadState {
+            status: ModelStatus::Downloading,
+            progress: 0.0,
+        });
+    }
+
+    let result = fetch_model_file(&model, &state, &app).await;
+
+    // 下载结束，更新该模型的状态
     {
         let mut inner = state.inner.lock()
             .map_err(|e| format!("获取状态锁失败: {}", e))?;
-        inner.model_status = ModelStatus::Downloading;
-        inner.download_progress = 0.0;
+        let entry = match &result {
+            Ok(()) => DownloadState { status: ModelStatus::Downloaded, progress: 1.0 },
+            Err(e) => DownloadState { status: ModelStatus::Error(e.clone()), progress: 0.0 },
+        };
+        inner.downloads.insert(name.clone(), entry);
+    }
+
+    match &result {
+        Ok(()) => {
+            let _ = app.emit("model-download-progress", DownloadProgressEvent {
+                model_name: name.clone(),
+                progress: 1.0,
+                status: "completed".to_string(),
+            });
+            log::info!("模型下载完成: {}", name);
+        }
+        Err(e) => {
+            let _ = app.emit("model-download-progress", DownloadProgressEvent {
+                model_name: name.clone(),
+                progress: 0.0,
+                status: "error".to_string(),
+            });
+            log::error!("模型 {} 下载失败: {}", name, e);
+        }
+    }
+
+    result
+}
+
+/// 检查模型下载站点是否可达（不下载模型，只请求首个字节）
+///
+/// model_name 为空时用 Tiny 模型的地址检测；设置了镜像时检测镜像站
+#[tauri::command]
+pub async fn check_model_host(model_name: Option<String>) -> Result<String, String> {
+    let model = match model_name {
+        Some(name) => WhisperModel::from_str(&name)
+            .ok_or_else(|| format!("未知的模型名称: {}", name))?,
+        None => WhisperModel::Tiny,
+    };
+    let url = model.download_url();
+    log::info!("检测模型下载站点: {}", url);
+
+    let client = crate::net::client_builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+    // 部分镜像不支持 HEAD，用 Range GET 只取 1 字节
+    let resp = client
+        .get(&url)
+        .header("Range", "bytes=0-0")
+        .send()
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if e.is_timeout() || msg.contains("timeout") {
+                "连接超时，站点可能被屏蔽，可在设置中填写模型镜像（如 https://hf-mirror.com）".to_string()
+            } else if msg.contains("dns") || msg.contains("resolve") {
+                "域名解析失败，请检查网络或模型镜像地址".to_string()
+            } else if e.is_connect() || msg.contains("connect") {
+                "无法连接到模型下载站点，可在设置中填写模型镜像（如 https://hf-mirror.com）".to_string()
+            } else {
+                format!("请求失败: {}", e)
+            }
+        })?;
+
+    let host = crate::whisper::model_host_base();
+    match resp.status().as_u16() {
+        200..=299 => Ok(format!("模型下载站点可达（{}）", host)),
+        404       => Err(format!("站点可达，但未找到模型文件 {}，请确认镜像地址", model.filename())),
+        401 | 403 => Err(format!("站点拒绝访问（{}）", resp.status())),
+        code      => Err(format!("站点返回异常状态: {}", code)),
     }
+}
+
+/// 检查模型目录所在磁盘是否有足够空间（预估大小 + 安全余量）
+fn check_disk_space(model: &WhisperModel) -> Result<(), String> {
+    let models_dir = get_models_dir()
+        .map_err(|e| format!("获取模型目录失败: {}", e))?;
 
-    let download_url = model.download_url();
-    let model_path = get_model_path(&model)
+    let available = match fs2::available_space(&models_dir) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            // 查询失败不阻止下载，交给写入阶段报错
+            log::warn!("无法查询磁盘可用空间: {}", e);
+            return Ok(());
+        }
+    };
+
+    let required = (model.approx_size_mb() as u64 + DISK_SPACE_MARGIN_MB) * 1024 * 1024;
+    log::info!(
+        "磁盘空间预检: 可用 {} MB, 需要 {} MB",
+        available / 1024 / 1024, required / 1024 / 1024
+    );
+
+    if available < required {
+        return Err(format!(
+            "磁盘空间不足，需要约 {} MB（下载 {}），当前可用 {} MB，还差约 {} MB",
+            required / 1024 / 1024,
+            model.display_name(),
+            available / 1024 / 1024,
+            (required - available).div_ceil(1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+/// 单个站点下载失败的类型：可换站点重试 / 不可重试（如应用退出、写盘失败）
+enum FetchError {
+    Retryable(String),
+    Fatal(String),
+}
+
+/// 下载模型文件：依次尝试各下载站点，连接失败 / 超时 / 服务端错误时换下一个站点
+///
+/// 先写入 .part 临时文件，校验大小和文件头后再 rename 为正式文件；
+/// 换站点时从头重新下载，避免拼接来自不同站点的数据
+async fn fetch_model_file(
+    model: &WhisperModel,
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
+) -> Result<(), String> {
+    let model_path = get_model_path(model)
         .map_err(|e| format!("获取模型路径失败: {}", e))?;
+    let partial = model_path.with_extension("bin.part");
+
+    let hosts = crate::whisper::model_host_candidates();
+    let mut errors = Vec::new();
+
+    for (attempt, host) in hosts.iter().enumerate() {
+        let _ = app.emit("model-download-mirror", DownloadMirrorEvent {
+            model_name: model.name().to_string(),
+            host: host.clone(),
+            attempt: attempt + 1,
+            total: hosts.len(),
+        });
 
-    log::info!("开始下载模型: {} -> {:?}", download_url, model_path);
+        let result = fetch_from_host(model, host, &partial, state, app).await;
+        if result.is_err() {
+            let _ = std::fs::remove_file(&partial);
+        }
+        match result {
+            Ok(()) => {
+                std::fs::rename(&partial, &model_path)
+                    .map_err(|e| format!("保存模型文件失败: {}", e))?;
+                return Ok(());
+            }
+            Err(FetchError::Fatal(e)) => return Err(e),
+            Err(FetchError::Retryable(e)) => {
+                log::warn!("从 {} 下载失败: {}", host, e);
+                errors.push(format!("{}: {}", host, e));
+            }
+        }
+    }
+
+    Err(format!("所有下载站点均失败：\n{}", errors.join("\n")))
+}
+
+/// 从指定站点流式下载到 partial 文件，完成后校验大小、ggml 文件头和内置的 SHA256
+async fn fetch_from_host(
+    model: &WhisperModel,
+    host: &str,
+    partial: &std::path::Path,
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
+) -> Result<(), FetchError> {
+    let name = model.name().to_string();
+    let download_url = model.download_url_from(host);
+
+    log::info!("开始下载模型: {} -> {:?}", download_url, partial);
 
-    // 发送进度事件
+    // 换站点时进度从 0 重新开始
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| FetchError::Fatal(format!("获取状态锁失败: {}", e)))?;
+        if let Some(entry) = inner.downloads.get_mut(&name) {
+            entry.progress = 0.0;
+        }
+    }
     let _ = app.emit("model-download-progress", DownloadProgressEvent {
-        model_name: model_name.clone(),
+        model_name: name.clone(),
         progress: 0.0,
         status: "downloading".to_string(),
     });
 
     // 执行下载
     // 注意：这里用 reqwest 的流式下载来跟踪进度
-    let client = reqwest::Client::new();
+    let client = crate::net::client_builder()
+        .connect_timeout(std::time::Duration::from_secs(DOWNLOAD_CONNECT_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| FetchError::Fatal(format!("创建 HTTP 客户端失败: {}", e)))?;
     let response = client
         .get(&download_url)
         .send()
         .await
-        .map_err(|e| format!("下载请求失败: {}", e))?;
+        .map_err(|e| FetchError::Retryable(format!("下载请求失败: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(FetchError::Retryable(format!("站点返回异常状态: {}", response.status())));
+    }
 
     let total_size = response.content_length().unwrap_or(0);
 
     // 流式写入文件
-    let mut file = std::fs::File::create(&model_path)
-        .map_err(|e| format!("创建文件失败: {}", e))?;
+    let mut file = std::fs::File::create(partial)
+        .map_err(|e| FetchError::Fatal(format!("创建文件失败: {}", e)))?;
 
     let mut downloaded: u64 = 0;
     let mut stream = response.bytes_stream();
+    // 边下载边计算摘要，省去下载完成后重新读取整个文件
+    let mut hasher = Sha256::new();
 
     use std::io::Write;
     use futures_util::StreamExt;
 
     while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("下载中断: {}", e))?;
+        let chunk = chunk.map_err(|e| FetchError::Retryable(format!("下载中断: {}", e)))?;
         file.write_all(&chunk)
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+            .map_err(|e| FetchError::Fatal(format!("写入文件失败: {}", e)))?;
+        hasher.update(&chunk);
 
         downloaded += chunk.len() as u64;
 
@@ -164,52 +383,84 @@ pub async fn download_model(
         // 每 5% 更新一次进度（避免过于频繁的事件）
         {
             let mut inner = state.inner.lock()
-                .map_err(|e| format!("获取状态锁失败: {}", e))?;
-            let old_progress = inner.download_progress;
-            if progress - old_progress > 0.05 || progress >= 1.0 {
-                inner.download_progress = progress;
-                let _ = app.emit("model-download-progress", DownloadProgressEvent {
-                    model_name: model_name.clone(),
-                    progress,
-                    status: "downloading".to_string(),
-                });
+                .map_err(|e| FetchError::Fatal(format!("获取状态锁失败: {}", e)))?;
+            if inner.shutting_down {
+                return Err(FetchError::Fatal("应用正在退出，下载已取消".to_string()));
+            }
+            if let Some(entry) = inner.downloads.get_mut(&name) {
+                if progress - entry.progress > 0.05 || progress >= 1.0 {
+                    entry.progress = progress;
+                    let _ = app.emit("model-download-progress", DownloadProgressEvent {
+                        model_name: name.clone(),
+                        progress,
+                        status: "downloading".to_string(),
+                    });
+                }
             }
         }
     }
-
-    // 下载完成，更新状态
-    {
-        let mut inner = state.inner.lock()
-            .map_err(|e| format!("获取状态锁失败: {}", e))?;
-        inner.model_status = ModelStatus::Downloaded;
-        inner.download_progress = 1.0;
-    }
+    file.sync_all()
+        .map_err(|e| FetchError::Fatal(format!("写入文件失败: {}", e)))?;
+    drop(file);
 
     let _ = app.emit("model-download-progress", DownloadProgressEvent {
-        model_name: model_name.clone(),
+        model_name: name.clone(),
         progress: 1.0,
-        status: "completed".to_string(),
+        status: "verifying".to_string(),
     });
 
-    log::info!("模型下载完成: {}", model_name);
+    // 校验：大小与 Content-Length 一致，文件头是 ggml（镜像可能返回错误页面），SHA256 与内置的一致
+    if total_size > 0 && downloaded != total_size {
+        return Err(FetchError::Retryable(format!(
+            "文件不完整（{} / {} 字节）", downloaded, total_size
+        )));
+    }
+    crate::whisper::validate_ggml_file(partial)
+        .map_err(|e| FetchError::Retryable(format!("下载的文件无效: {}", e)))?;
+
+    // 摘要不一致：文件内容有误，换站点也无意义，由 fetch_model_file 删除 .part 文件后报错
+    let actual_sha256 = format!("{:x}", hasher.finalize());
+    let expected_sha256 = model.expected_sha256();
+    if actual_sha256 != expected_sha256 {
+        log::error!("模型 {} SHA256 不一致：预期 {}，实际 {}", name, expected_sha256, actual_sha256);
+        return Err(FetchError::Fatal(format!(
+            "模型 {} 校验失败（SHA256 不一致），已删除下载的文件", model.display_name()
+        )));
+    }
+    log::info!("模型 {} SHA256 校验通过: {}", name, actual_sha256);
     Ok(())
 }
 
+/// 切换下载站点事件数据
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadMirrorEvent {
+    pub model_name: String,
+    pub host: String,
+    /// 第几个站点（从 1 开始）/ 共几个
+    pub attempt: usize,
+    pub total: usize,
+}
+
 /// 下载进度事件数据
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DownloadProgressEvent {
     pub model_name: String,
     pub progress: f64,   // 0.0 - 1.0
-    pub status: String,  // "downloading" / "completed" / "error"
+    pub status: String,  // "downloading" / "verifying" / "completed" / "error"
 }
 
 /// 手动将指定模型加载到内存
 /// 让用户可以提前加载，避免第一次识别时因加载模型产生无反馈的长时等待
+///
+/// force: 可用内存低于模型预估占用时默认拒绝加载，前端确认后传 true 强制加载
 #[tauri::command]
 pub async fn load_whisper_model(
     model_name: String,
+    force: Option<bool>,
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
 ) -> Result<(), String> {
     let model = crate::whisper::WhisperModel::from_str(&model_name)
         .ok_or_else(|| format!("未知模型: {}", model_name))?;
@@ -231,6 +482,24 @@ pub async fn load_whisper_model(
         }
     }
 
+    // 内存预检：低内存机器上加载大模型可能导致严重换页甚至崩溃
+    // 读不到内存信息时返回 0，视为未知，跳过检查
+    let available_ram_mb = crate::whisper::available_memory_mb();
+    if available_ram_mb == 0 {
+        log::warn!("无法获取可用内存，跳过 {} 的内存预检", model.display_name());
+    } else if model.required_ram_mb() > available_ram_mb {
+        if !force.unwrap_or(false) {
+            return Err(format!(
+                "内存可能不足：{} 预计占用约 {} MB，当前可用 {} MB。确认后仍可强制加载",
+                model.display_name(), model.required_ram_mb(), available_ram_mb
+            ));
+        }
+        log::warn!(
+            "强制加载 {}：预计占用 {} MB，可用仅 {} MB",
+            model.display_name(), model.required_ram_mb(), available_ram_mb
+        );
+    }
+
     // 设置状态为加载中
     {
         let mut inner = state.inner.lock()
@@ -240,36 +509,343 @@ pub async fn load_whisper_model(
 
     log::info!("手动加载 Whisper 模型: {}", model.display_name());
 
+    // 引擎会先释放旧模型再加载新模型，失败时内存中已无模型
+    if let Err(e) = load_model_with_progress(&app, &state, &model, model_path).await {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.model_status = ModelStatus::Error(e.clone());
+        return Err(e);
+    }
+
+    // 加载完成
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.model_status = ModelStatus::Ready;
+        inner.model_last_used = Some(std::time::Instant::now());
+    }
+
+    log::info!("模型 {} 手动加载完成", model.display_name());
+    Ok(())
+}
+
+/// 模型加载进度事件数据
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelLoadProgressEvent {
+    pub model_name: String,
+    /// "reading" / "initializing" / "loading"（心跳）/ "ready" / "error"
+    pub phase: String,
+    /// 自开始加载以来的耗时
+    pub elapsed_ms: u64,
+}
+
+/// 在大栈线程中加载模型，期间通过 model-load-progress 事件报告阶段和心跳
+///
+/// whisper.cpp 不提供细粒度加载进度，这里只在 FFI 调用前后报告阶段，
+/// 等待期间每秒发送一次心跳，供 load_whisper_model 和 transcribe_audio 共用
+pub(crate) async fn load_model_with_progress(
+    app: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+    model: &WhisperModel,
+    model_path: std::path::PathBuf,
+) -> Result<(), String> {
+    let model_name = model.name().to_string();
+    let started = std::time::Instant::now();
+
+    let emit_phase = {
+        let app = app.clone();
+        let model_name = model_name.clone();
+        move |phase: &str| {
+            let _ = app.emit("model-load-progress", ModelLoadProgressEvent {
+                model_name: model_name.clone(),
+                phase: phase.to_string(),
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+    };
+
     // 模型加载：使用大栈线程（32MB），避免 Windows 默认 1MB 栈溢出
     let whisper_arc = state.whisper.clone();
-    let (load_tx, load_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
+    let thread_emit = emit_phase.clone();
+    let (load_tx, mut load_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
     std::thread::Builder::new()
         .name("whisper-model-load".to_string())
-        .stack_size(32 * 1024 * 1024)
+        .stack_size(LOAD_STACK_SIZE)
         .spawn(move || {
-            let result = (|| -> Result<(), String> {
+            // catch_unwind：线程内 panic 转为命令错误，而不是只表现为通道断开
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| -> Result<(), String> {
                 let mut eng = whisper_arc.lock()
                     .map_err(|e| format!("引擎锁失败: {}", e))?;
-                eng.load_model(&model_path)
+                eng.load_model_with_phases(&model_path, |phase| thread_emit(phase))
                     .map_err(|e| format!("加载模型失败: {}", e))
-            })();
+            }))
+            .unwrap_or_else(|payload| Err(format!(
+                "模型加载线程崩溃: {}", crate::panic_message(payload.as_ref())
+            )));
             let _ = load_tx.send(result);
         })
         .map_err(|e| format!("创建加载线程失败: {}", e))?;
 
-    load_rx.await
-        .map_err(|e| format!("加载线程通信失败: {}", e))
-        .and_then(|r| r)?;
+    // 等待加载完成，期间定时发送心跳
+    let mut heartbeat = tokio::time::interval(std::time::Duration::from_millis(LOAD_HEARTBEAT_MS));
+    heartbeat.tick().await; // 第一次 tick 立即返回，跳过
+    let result = loop {
+        tokio::select! {
+            r = &mut load_rx => {
+                break r
+                    .map_err(|e| format!("加载线程通信失败: {}", e))
+                    .and_then(|r| r);
+            }
+            _ = heartbeat.tick() => emit_phase("loading"),
+        }
+    };
 
-    // 加载完成
-    {
+    // 同步已加载模型名（加载失败时旧模型已被卸载）
+    if let Ok(mut inner) = state.inner.lock() {
+        inner.loaded_model_file = result.is_ok().then(|| model.filename().to_string());
+    }
+
+    emit_phase(if result.is_ok() { "ready" } else { "error" });
+    result
+}
+
+// ===== 性能测试 =====
+
+/// 合成参考音频的时长（秒）
+const BENCHMARK_CLIP_SECS: f64 = 10.0;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+    pub model_name: String,
+    /// 测试音频时长（毫秒）
+    pub audio_ms: u64,
+    /// 推理耗时（毫秒，不含模型加载）
+    pub wall_ms: u64,
+    /// 实时率 = 推理耗时 / 音频时长（< 1 表示比实时快）
+    pub realtime_factor: f64,
+    /// 推理后端（Metal / CUDA / CPU）
+    pub backend: String,
+    pub threads: i32,
+    /// 测试音频来源："lastRecording"（最近一次录音）或 "synthetic"（合成参考音频）
+    pub clip_source: String,
+}
+
+/// 模型性能测试：加载模型并识别一段参考音频，返回实时率
+///
+/// 优先使用最近一次录音（真实语音，结果更贴近实际）；没有录音时使用合成的参考音频。
+/// 测试后该模型保持加载状态（引擎同一时间只保留一个模型）
+#[tauri::command]
+pub async fn benchmark_model(
+    model_name: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<BenchmarkResult, String> {
+    let model = WhisperModel::from_str(&model_name)
+        .ok_or_else(|| format!("未知的模型名称: {}", model_name))?;
+
+    if !is_model_downloaded(&model) {
+        return Err(format!("模型 {} 尚未下载，请先下载", model.display_name()));
+    }
+
+    let (clip, clip_source, timeout_cap) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        if inner.recording_status != crate::state::RecordingStatus::Idle {
+            return Err("正在录音或识别中，请稍后再测试".to_string());
+        }
+        let cap = inner.settings.inference_timeout_cap_secs;
+        match &inner.last_recording {
+            Some(rec) if rec.len() >= 16000 => (rec.clone(), "lastRecording", cap),
+            _ => (synthetic_reference_clip(BENCHMARK_CLIP_SECS), "synthetic", cap),
+        }
+    };
+
+    // 加载模型（已加载则跳过）
+    let needs_load = {
+        let eng = state.whisper.lock()
+            .map_err(|e| format!("引擎锁失败: {}", e))?;
+        eng.current_model_name() != Some(model.filename())
+    };
+    if needs_load {
+        let model_path = get_model_path(&model)
+            .map_err(|e| format!("获取模型路径失败: {}", e))?;
+        {
+            let mut inner = state.inner.lock()
+                .map_err(|e| format!("状态锁失败: {}", e))?;
+            inner.model_status = ModelStatus::Loading;
+        }
+        let loaded = load_model_with_progress(&app, &state, &model, model_path).await;
         let mut inner = state.inner.lock()
             .map_err(|e| format!("状态锁失败: {}", e))?;
-        inner.model_status = ModelStatus::Ready;
+        inner.model_status = match &loaded {
+            Ok(()) => ModelStatus::Ready,
+            Err(e) => ModelStatus::Error(e.clone()),
+        };
+        loaded?;
     }
 
-    log::info!("模型 {} 手动加载完成", model.display_name());
-    Ok(())
+    let audio_duration_s = clip.len() as f64 / 16000.0;
+    let timeout_secs = crate::commands::transcribe::inference_timeout_secs(&model, audio_duration_s, timeout_cap);
+    log::info!(
+        "开始性能测试: {}, 音频 {:.1} 秒（{}）",
+        model.display_name(), audio_duration_s, clip_source
+    );
+
+    // 与正常识别相同：大栈线程 + oneshot channel
+    let whisper_arc = state.whisper.clone();
+    let (tx, rx) = tokio::sync::oneshot::channel::<Result<u64, String>>();
+    std::thread::Builder::new()
+        .name("whisper-benchmark".to_string())
+        .stack_size(crate::commands::transcribe::INFERENCE_STACK_SIZE)
+        .spawn(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| -> Result<u64, String> {
+                let eng = whisper_arc.lock()
+                    .map_err(|e| format!("引擎锁失败: {}", e))?;
+                let started = std::time::Instant::now();
+                eng.transcribe(&clip, "auto")
+                    .map_err(|e| format!("测试识别失败: {}", e))?;
+                Ok(started.elapsed().as_millis() as u64)
+            }))
+            .unwrap_or_else(|payload| Err(format!(
+                "测试线程崩溃: {}", crate::panic_message(payload.as_ref())
+            )));
+            let _ = tx.send(result);
+        })
+        .map_err(|e| format!("创建测试线程失败: {}", e))?;
+
+    let wall_ms = match timeout_secs {
+        Some(timeout_secs) => match tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), rx).await {
+            Ok(Ok(result)) => result?,
+            Ok(Err(e)) => return Err(format!("测试线程通信失败: {}", e)),
+            Err(_) => return Err(format!("性能测试超时（已等待 {} 秒），该模型在本机上过慢", timeout_secs)),
+        },
+        // 超时上限为 0：不限制超时
+        None => rx.await.map_err(|e| format!("测试线程通信失败: {}", e))??,
+    };
+
+    let audio_ms = (audio_duration_s * 1000.0) as u64;
+    let realtime_factor = wall_ms as f64 / audio_ms as f64;
+    log::info!(
+        "性能测试完成: {} 耗时 {} ms，实时率 {:.2}x",
+        model.display_name(), wall_ms, realtime_factor
+    );
+
+    Ok(BenchmarkResult {
+        model_name: model.name().to_string(),
+        audio_ms,
+        wall_ms,
+        realtime_factor,
+        backend: crate::whisper::compute_backend().to_string(),
+        threads: crate::whisper::inference_threads(),
+        clip_source: clip_source.to_string(),
+    })
+}
+
+/// 合成参考音频（16kHz 单声道）：基频 150Hz 加谐波，按约 4Hz 的音节节奏调幅
+/// 只用于测量推理速度 / 测试接口连通，内容本身没有意义
+pub(crate) fn synthetic_reference_clip(secs: f64) -> Vec<f32> {
+    let n = (secs * 16000.0) as usize;
+    (0..n)
+        .map(|i| {
+            let t = i as f64 / 16000.0;
+            let voice: f64 = (1..=5)
+                .map(|k| (2.0 * std::f64::consts::PI * 150.0 * k as f64 * t).sin() / k as f64)
+                .sum();
+            let envelope = 0.5 * (1.0 - (2.0 * std::f64::consts::PI * 4.0 * t).cos());
+            (0.1 * voice * envelope) as f32
+        })
+        .collect()
+}
+
+/// 查询当前内存中的模型
+#[tauri::command]
+pub async fn get_active_model(
+    state: State<'_, AppState>,
+) -> Result<ActiveModelResponse, String> {
+    active_model_snapshot(&state)
+}
+
+/// 只读 inner 中的副本组装当前模型信息，推理期间 whisper 锁被占用也能立即返回
+fn active_model_snapshot(state: &AppState) -> Result<ActiveModelResponse, String> {
+    let (file_name, status) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        (inner.loaded_model_file.clone(), inner.model_status.clone())
+    };
+
+    let model = file_name.as_deref().and_then(|f| {
+        WhisperModel::all().into_iter().find(|m| m.filename() == f)
+    });
+
+    Ok(ActiveModelResponse {
+        loaded: file_name.is_some(),
+        model_name: model.map(|m| m.name().to_string()),
+        display_name: model.map(|m| m.display_name().to_string()),
+        file_name,
+        status,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveModelResponse {
+    /// 是否有模型在内存中
+    pub loaded: bool,
+    /// 模型名（如 "small"），自定义/导入的模型文件为 None
+    pub model_name: Option<String>,
+    pub display_name: Option<String>,
+    /// 已加载的模型文件名（如 ggml-small.bin）
+    pub file_name: Option<String>,
+    pub status: ModelStatus,
+}
+
+/// 空闲自动卸载的检查间隔（秒）
+const IDLE_UNLOAD_CHECK_SECS: u64 = 10;
+
+/// 启动空闲自动卸载任务：模型超过 unload_after_idle_secs 未使用时卸载，
+/// 下次识别时由 transcribe_audio 自动重新加载
+pub(crate) fn spawn_idle_unloader(app: tauri::AppHandle) {
+    use tauri::Manager;
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(IDLE_UNLOAD_CHECK_SECS)).await;
+            let state = app.state::<AppState>();
+
+            let idle_expired = {
+                let Ok(inner) = state.inner.lock() else { continue };
+                let limit = inner.settings.unload_after_idle_secs;
+                limit > 0
+                    && inner.recording_status == crate::state::RecordingStatus::Idle
+                    && inner.model_status == ModelStatus::Ready
+                    && inner.model_last_used
+                        .map(|t| t.elapsed().as_secs() >= limit)
+                        .unwrap_or(true)
+            };
+            if !idle_expired {
+                continue;
+            }
+
+            // 推理 / 加载线程持有引擎锁时跳过本轮，不阻塞等待
+            let unloaded = match state.whisper.try_lock() {
+                Ok(mut eng) if eng.is_loaded() => {
+                    eng.unload();
+                    true
+                }
+                _ => false,
+            };
+            if unloaded {
+                if let Ok(mut inner) = state.inner.lock() {
+                    inner.model_status = ModelStatus::Downloaded;
+                    inner.model_last_used = None;
+                    inner.loaded_model_file = None;
+                }
+                log::info!("模型空闲超时，已自动卸载");
+            }
+        }
+    });
 }
 
 /// 卸载模型（从内存中释放，保留磁盘文件）
@@ -292,15 +868,100 @@ pub async fn unload_whisper_model(
         let mut inner = state.inner.lock()
             .map_err(|e| format!("状态锁失败: {}", e))?;
         inner.model_status = ModelStatus::Downloaded;
+        inner.loaded_model_file = None;
     }
 
     log::info!("Whisper 模型已从内存卸载");
     Ok(())
 }
 
+/// 导入磁盘上已有的 ggml 模型文件（如其他应用下载过的），避免重复下载
+///
+/// - path 为空时弹出文件选择框（只显示 .bin），用户取消时返回 None
+/// - model_name 为空时按文件名识别（需为 ggml-tiny.bin 这类标准文件名）
+/// - 优先硬链接到模型目录（不占额外空间），跨磁盘等情况失败时复制
+#[tauri::command]
+pub async fn import_model(
+    path: Option<String>,
+    model_name: Option<String>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Option<String>, String> {
+    let source = match path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => std::path::PathBuf::from(path.trim()),
+        None => {
+            use tauri_plugin_dialog::DialogExt;
+            // 对话框会阻塞到用户选择完成，放到阻塞线程中等待
+            let dialog = app.dialog().file().add_filter("GGML", &["bin"]);
+            let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_pick_file())
+                .await
+                .map_err(|e| format!("文件选择框异常: {}", e))?;
+            let Some(picked) = picked else {
+                return Ok(None);
+            };
+            picked.into_path()
+                .map_err(|e| format!("文件路径无效: {}", e))?
+        }
+    };
+
+    let model = match model_name {
+        Some(name) => WhisperModel::from_str(&name)
+            .ok_or_else(|| format!("未知的模型名称: {}", name))?,
+        None => {
+            let file_name = source.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            WhisperModel::all()
+                .into_iter()
+                .find(|m| m.filename().eq_ignore_ascii_case(file_name))
+                .ok_or_else(|| format!("无法从文件名 {} 判断模型类型，请手动指定模型", file_name))?
+        }
+    };
+
+    let size = crate::whisper::validate_ggml_file(&source)
+        .map_err(|e| format!("模型文件无效: {}", e))?;
+
+    if is_model_downloaded(&model) {
+        return Err(format!("模型 {} 已存在，如需替换请先删除", model.display_name()));
+    }
+
+    let target = get_model_path(&model)
+        .map_err(|e| format!("获取模型路径失败: {}", e))?;
+
+    if std::fs::hard_link(&source, &target).is_ok() {
+        log::info!("已通过硬链接导入模型: {:?} -> {:?}", source, target);
+    } else {
+        // 先复制到临时文件再改名，避免复制中断留下不完整的模型文件
+        let partial = target.with_extension("bin.part");
+        let copied = std::fs::copy(&source, &partial)
+            .and_then(|_| std::fs::rename(&partial, &target));
+        if let Err(e) = copied {
+            let _ = std::fs::remove_file(&partial);
+            return Err(format!("复制模型文件失败: {}", e));
+        }
+        log::info!("已复制导入模型: {:?} -> {:?} ({:.1} MB)", source, target, size as f64 / 1024.0 / 1024.0);
+    }
+
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        inner.downloads.insert(model.name().to_string(), DownloadState {
+            status: ModelStatus::Downloaded,
+            progress: 1.0,
+        });
+    }
+    notify_models_changed(&app, &state).await?;
+
+    Ok(Some(model.name().to_string()))
+}
+
 /// 删除模型文件
 #[tauri::command]
-pub async fn delete_model(model_name: String) -> Result<(), String> {
+pub async fn delete_model(
+    model_name: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
     let model = WhisperModel::from_str(&model_name)
         .ok_or_else(|| format!("未知的模型名称: {}", model_name))?;
 
@@ -312,6 +973,189 @@ pub async fn delete_model(model_name: String) -> Result<(), String> {
             .map_err(|e| format!("删除模型文件失败: {}", e))?;
         log::info!("已删除模型: {:?}", path);
     }
+    notify_models_changed(&app, &state).await?;
 
     Ok(())
 }
+
+/// 重新扫描模型目录，同步应用外部增删的模型文件
+///
+/// - 各模型的下载状态按磁盘实际情况更新（下载中的不动）
+/// - 已加载模型的文件被删除时仍保留在内存中可用，只更新磁盘状态
+/// - 发送 models-changed 事件，返回最新的模型列表
+#[tauri::command]
+pub async fn refresh_models(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Vec<ModelInfo>, String> {
+    notify_models_changed(&app, &state).await
+}
+
+/// 按磁盘实际情况同步模型状态，并广播 models-changed
+async fn notify_models_changed(
+    app: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+) -> Result<Vec<ModelInfo>, String> {
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+
+        for model in WhisperModel::all() {
+            let on_disk = is_model_downloaded(&model);
+            let status = inner.downloads.get(model.name()).map(|d| d.status.clone());
+            match status {
+                Some(ModelStatus::Downloading) => {}
+                _ if on_disk => {
+                    inner.downloads.insert(model.name().to_string(), DownloadState {
+                        status: ModelStatus::Downloaded,
+                        progress: 1.0,
+                    });
+                }
+                Some(ModelStatus::Downloaded) => {
+                    inner.downloads.remove(model.name());
+                }
+                _ => {}
+            }
+        }
+
+        // 选中模型的整体状态：已加载 / 加载中保持不变（文件被删但内存中仍可用）
+        let selected_on_disk = WhisperModel::from_str(&inner.settings.local_model)
+            .is_some_and(|m| is_model_downloaded(&m));
+        match inner.model_status {
+            ModelStatus::Downloaded if !selected_on_disk => {
+                inner.model_status = ModelStatus::NotDownloaded;
+            }
+            ModelStatus::NotDownloaded if selected_on_disk => {
+                inner.model_status = ModelStatus::Downloaded;
+            }
+            _ => {}
+        }
+
+        if let Some(loaded) = &inner.loaded_model_file {
+            let loaded_on_disk = get_models_dir()
+                .map(|dir| dir.join(loaded).exists())
+                .unwrap_or(false);
+            if !loaded_on_disk {
+                log::warn!("已加载模型的文件 {} 已从磁盘删除，卸载后需重新下载", loaded);
+            }
+        }
+    }
+
+    let models = list_models().await?;
+    let _ = app.emit("models-changed", &models);
+    Ok(models)
+}
+
+/// 更改模型存储目录
+///
+/// dir: 新目录（None 或空字符串 = 恢复默认目录）
+/// move_existing: true 时把旧目录中已下载的模型移动到新目录；false 时只切换并重新扫描
+#[tauri::command]
+pub async fn set_models_dir(
+    dir: Option<String>,
+    move_existing: bool,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Vec<ModelInfo>, String> {
+    let new_dir = dir
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .map(std::path::PathBuf::from);
+
+    if let Some(d) = &new_dir {
+        crate::whisper::validate_models_dir(d)
+            .map_err(|e| format!("模型目录无效: {}", e))?;
+    }
+
+    let old_dir = get_models_dir()
+        .map_err(|e| format!("获取模型目录失败: {}", e))?;
+
+    // 先算出目标目录，移动和持久化都成功后才切换全局覆盖，失败时仍指向旧目录
+    let target_dir = match &new_dir {
+        Some(d) => d.clone(),
+        None => {
+            let d = crate::whisper::default_models_dir()
+                .map_err(|e| format!("获取模型目录失败: {}", e))?;
+            std::fs::create_dir_all(&d)
+                .map_err(|e| format!("无法创建模型目录 {:?}: {}", d, e))?;
+            d
+        }
+    };
+
+    if move_existing && old_dir != target_dir {
+        for model in WhisperModel::all() {
+            let src = old_dir.join(model.filename());
+            let dst = target_dir.join(model.filename());
+            if !src.exists() || dst.exists() {
+                continue;
+            }
+            // rename 跨磁盘会失败，此时退回 复制 + 删除
+            if std::fs::rename(&src, &dst).is_err() {
+                std::fs::copy(&src, &dst)
+                    .map_err(|e| format!("移动模型 {} 失败: {}", model.display_name(), e))?;
+                let _ = std::fs::remove_file(&src);
+            }
+            log::info!("已移动模型 {:?} -> {:?}", src, dst);
+        }
+    }
+
+    let mut settings = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.settings.clone()
+    };
+    settings.models_dir = new_dir.clone();
+    crate::commands::settings::persist_settings(&app, &settings)?;
+
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.settings.models_dir = new_dir.clone();
+    }
+    crate::whisper::set_models_dir_override(new_dir);
+
+    log::info!("模型目录已切换为 {:?}", target_dir);
+    list_models().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn active_model_query_does_not_wait_for_whisper_lock() {
+        let state = std::sync::Arc::new(AppState::new());
+        state.inner.lock().unwrap().loaded_model_file =
+            Some(WhisperModel::Small.filename().to_string());
+
+        // 模拟推理线程长时间持有 whisper 锁
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let whisper = state.whisper.clone();
+        let holder = std::thread::spawn(move || {
+            let _guard = whisper.lock().unwrap();
+            locked_tx.send(()).unwrap();
+            let _ = release_rx.recv();
+        });
+        locked_rx.recv().unwrap();
+
+        let (done_tx, done_rx) = mpsc::channel();
+        let query_state = state.clone();
+        std::thread::spawn(move || {
+            let _ = done_tx.send(active_model_snapshot(&query_state));
+        });
+        let resp = done_rx
+            .recv_timeout(Duration::from_secs(2))
+            .expect("状态查询被 whisper 锁阻塞")
+            .unwrap();
+
+        assert!(resp.loaded);
+        assert_eq!(resp.file_name.as_deref(), Some("ggml-small.bin"));
+        assert_eq!(resp.model_name.as_deref(), Some(WhisperModel::Small.name()));
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+    }
+}
@@ -1,12 +1,15 @@
 // commands/settings.rs - 应用设置命令（含磁盘持久化）
 
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_store::StoreExt;
-use crate::state::{AppState, AppSettings};
+use crate::state::{AppState, AppSettings, TranscriptionMode};
 
-const STORE_FILE: &str = "voxie-settings.json";
+pub(crate) const STORE_FILE: &str = "voxie-settings.json";
 const STORE_KEY:  &str = "settings";
 
+/// 设置写盘防抖间隔（毫秒）：滑块 / 输入框连续保存时合并为一次写入
+const PERSIST_DEBOUNCE_MS: u64 = 500;
+
 /// 获取当前设置
 #[tauri::command]
 pub async fn get_settings(
@@ -19,24 +22,233 @@ pub async fn get_settings(
 
 /// 保存设置到内存 + 磁盘
 /// 前端调用：invoke('save_settings', { settings: {...} })
+///
+/// 内存立即更新；磁盘写入防抖，连续保存时最多每 500ms 写一次（写入最新值）
 #[tauri::command]
 pub async fn save_settings(
     app:      AppHandle,
     settings: AppSettings,
     state:    State<'_, AppState>,
 ) -> Result<(), String> {
+    // 0. 先完成全部校验，任何一项失败都不改动全局覆盖（模型目录 / 镜像 / 线程数 / 代理）
+    if let Some(dir) = &settings.models_dir {
+        crate::whisper::validate_models_dir(dir)
+            .map_err(|e| format!("模型目录无效: {}", e))?;
+    }
+
+    // 校验模型下载镜像
+    if let Some(base) = settings.model_mirror_base.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+        crate::whisper::validate_model_mirror(base)?;
+    }
+
+    // 校验 HTTP 代理
+    if let Some(proxy) = settings.http_proxy.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        crate::net::validate_proxy(proxy)?;
+    }
+
+    // 校验推理超时上限（0 = 不限制）
+    let max_timeout = crate::commands::transcribe::MAX_INFERENCE_TIMEOUT_CAP_SECS;
+    if settings.inference_timeout_cap_secs > max_timeout {
+        return Err(format!(
+            "推理超时上限过大: {} 秒（最大 {} 秒，0 表示不限制）",
+            settings.inference_timeout_cap_secs, max_timeout
+        ));
+    }
+
+    // 校验统计上报地址
+    if let Some(url) = settings.metrics_endpoint.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("统计上报地址无效: {}（需以 http:// 或 https:// 开头）", url));
+        }
+    }
+
+    // 校验时间前缀格式（无效格式串在格式化时会 panic）
+    if crate::commands::transcribe::parse_timestamp_format(&settings.timestamp_prefix_format).is_none() {
+        return Err(format!("时间前缀格式无效: {}", settings.timestamp_prefix_format));
+    }
+
+    // 应用录音器和开机自启动：任何一步失败都按旧设置回滚已生效的部分，录音器 / 系统状态与内存中的设置保持一致
+    let previous = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        inner.settings.clone()
+    };
+    if let Err(e) = apply_live_settings(&app, &state, &settings) {
+        if let Err(rollback_err) = apply_live_settings(&app, &state, &previous) {
+            log::warn!("回滚录音 / 自启动设置失败: {}", rollback_err);
+        }
+        return Err(e);
+    }
+
+    // 以上均成功后才应用全局覆盖
+    crate::whisper::set_models_dir_override(settings.models_dir.clone());
+    crate::whisper::set_model_mirror_override(settings.model_mirror_base.clone());
+    crate::whisper::set_threads_override(settings.whisper_threads);
+    crate::net::set_proxy_override(settings.http_proxy.clone());
+
+    // 悬浮窗置顶
+    if let Err(e) = crate::commands::window::apply_always_on_top(&app, settings.always_on_top) {
+        log::warn!("{}", e);
+    }
+
+    // 录音快捷键（有变化时重新注册）：注册失败不阻止保存其他设置，通过 config-warning 提示
+    let shortcut_changed = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        inner.settings.shortcut_key != settings.shortcut_key
+    };
+    if shortcut_changed {
+        if let Err(e) = crate::commands::shortcut::apply_shortcut(&app, &settings.shortcut_key) {
+            log::warn!("{}", e);
+            let _ = app.emit("config-warning", &e);
+        }
+    }
+
     // 1. 更新内存
     {
         let mut inner = state.inner.lock()
             .map_err(|e| format!("获取状态锁失败: {}", e))?;
-        inner.settings = settings.clone();
+        inner.settings = settings;
+    }
+
+    // 2. 持久化到磁盘（防抖）
+    schedule_persist_settings(&app, &state)
+}
+
+/// 把设置中直接作用于录音器和系统的部分应用到运行状态（音频后端、输入设备、预录、裁剪、噪声门、开机自启动）
+///
+/// 先切换后端和设备，预录流按新配置打开；save_settings 失败回滚时用旧设置再调用一次
+fn apply_live_settings(app: &AppHandle, state: &AppState, settings: &AppSettings) -> Result<(), String> {
+    crate::commands::audio::apply_audio_host(state, settings.audio_host.clone())?;
+    crate::commands::audio::apply_input_device(state, settings.input_device.clone())?;
+    crate::commands::audio::apply_pre_roll(state, settings.pre_roll_ms)?;
+    crate::commands::audio::apply_trim_silence(state, settings.trim_silence_threshold)?;
+    crate::commands::audio::apply_noise_gate(state, settings.noise_gate_threshold)?;
+    crate::commands::autostart::apply_autostart(app, settings.autostart)
+}
+
+/// 立即把内存中的设置写入磁盘（如退出前），取消等待中的防抖写入
+#[tauri::command]
+pub async fn flush_settings(
+    app:   AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    flush_pending_settings(&app, &state, true)
+}
+
+/// 快速切换识别语言（无需打开设置页完整保存）
+/// 可由托盘菜单或快捷键调用，切换后广播 language-changed 事件
+#[tauri::command]
+pub async fn set_language(
+    app:   AppHandle,
+    lang:  String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let lang = lang.trim().to_lowercase();
+    if !crate::whisper::is_supported_language(&lang) {
+        return Err(format!("不支持的语言代码: {}", lang));
+    }
+
+    let settings = {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        inner.settings.language = lang.clone();
+        inner.settings.clone()
+    };
+
+    persist_settings(&app, &settings)?;
+    let _ = app.emit("language-changed", &lang);
+
+    log::info!("识别语言已切换为 {}", lang);
+    Ok(())
+}
+
+/// 快速切换识别模式（本地 / 云端），供托盘菜单或快捷键调用
+///
+/// 切到云端但凭据不完整时仍然切换，同时发送 config-warning 提醒；
+/// 切换后广播 mode-changed 事件，设置防抖写盘
+#[tauri::command]
+pub async fn set_transcription_mode(
+    app:   AppHandle,
+    mode:  TranscriptionMode,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let warning = {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        inner.settings.mode = mode.clone();
+        match mode {
+            TranscriptionMode::Cloud => {
+                crate::commands::transcribe::check_cloud_credentials(&inner.settings).err()
+            }
+            TranscriptionMode::Local => None,
+        }
+    };
+
+    schedule_persist_settings(&app, &state)?;
+    let _ = app.emit("mode-changed", &mode);
+    if let Some(warning) = warning {
+        log::warn!("配置提示: {}", warning);
+        let _ = app.emit("config-warning", &warning);
     }
 
-    // 2. 持久化到 JSON 文件（tauri-plugin-store 存入 app 数据目录）
+    log::info!("识别模式已切换为 {:?}", mode);
+    Ok(())
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageInfo {
+    /// whisper 语言代码（如 "zh"），"auto" 表示自动检测
+    pub code: String,
+    /// 英文名称（如 "chinese"）
+    pub name: String,
+    /// 当前模型能否识别该语言（仅英语模型下只有 en / auto 可用）
+    pub available: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportedLanguages {
+    /// 本地识别选择的是仅英语的 .en 模型
+    pub english_only: bool,
+    pub languages: Vec<LanguageInfo>,
+}
+
+/// 列出 whisper 支持的语言（首项为 auto），供前端语言下拉框使用
+#[tauri::command]
+pub async fn list_supported_languages(
+    state: State<'_, AppState>,
+) -> Result<SupportedLanguages, String> {
+    let english_only = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        let settings = &inner.settings;
+        // 只看用户选择的模型：prefer_english_models 自动换用的 .en 模型在切换语言后会换回
+        settings.mode == TranscriptionMode::Local
+            && crate::whisper::WhisperModel::from_str(&settings.local_model)
+                .is_some_and(|m| m.is_english_only())
+    };
+
+    let languages = std::iter::once(("auto", "auto"))
+        .chain(crate::whisper::SUPPORTED_LANGUAGES.iter().copied())
+        .map(|(code, name)| LanguageInfo {
+            code: code.to_string(),
+            name: name.to_string(),
+            available: !english_only || code == "en" || code == "auto",
+        })
+        .collect();
+
+    Ok(SupportedLanguages { english_only, languages })
+}
+
+/// 将设置持久化到 JSON 文件（tauri-plugin-store 存入 app 数据目录）
+/// 供 save_settings 及各个单项设置命令共用
+pub(crate) fn persist_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
     let store = app.store(STORE_FILE)
         .map_err(|e| format!("打开存储失败: {}", e))?;
 
-    let val = serde_json::to_value(&settings)
+    let val = serde_json::to_value(settings)
         .map_err(|e| format!("序列化设置失败: {}", e))?;
 
     store.set(STORE_KEY, val);
@@ -47,6 +259,43 @@ pub async fn save_settings(
     Ok(())
 }
 
+/// 安排一次防抖写盘：已有等待中的写入时直接合并，到期后写入届时最新的设置
+pub(crate) fn schedule_persist_settings(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        if inner.settings_persist_pending {
+            return Ok(());
+        }
+        inner.settings_persist_pending = true;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(PERSIST_DEBOUNCE_MS)).await;
+        let state = app.state::<AppState>();
+        if let Err(e) = flush_pending_settings(&app, &state, false) {
+            log::error!("设置写盘失败: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// 写入等待中的设置；force 为 true 时即使没有等待中的写入也写一次
+/// 供防抖任务、flush_settings 命令和应用退出时共用
+pub(crate) fn flush_pending_settings(app: &AppHandle, state: &AppState, force: bool) -> Result<(), String> {
+    let settings = {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("获取状态锁失败: {}", e))?;
+        if !inner.settings_persist_pending && !force {
+            return Ok(());
+        }
+        inner.settings_persist_pending = false;
+        inner.settings.clone()
+    };
+    persist_settings(app, &settings)
+}
+
 /// 应用启动时从磁盘加载持久化设置，写入 AppState
 /// 由 lib.rs setup() 调用
 pub fn load_persisted_settings(app: &AppHandle, state: &AppState) {
@@ -67,7 +316,34 @@ pub fn load_persisted_settings(app: &AppHandle, state: &AppState) {
     };
 
     match serde_json::from_value::<AppSettings>(val) {
-        Ok(settings) => {
+        Ok(mut settings) => {
+            // 旧版默认快捷键是单独的 "Alt"，无法注册为全局快捷键，迁移为默认组合键
+            if crate::commands::shortcut::is_bare_modifier(&settings.shortcut_key) {
+                log::info!(
+                    "快捷键 {} 只有修饰键，已迁移为 {}",
+                    settings.shortcut_key, crate::commands::shortcut::DEFAULT_SHORTCUT_KEY
+                );
+                settings.shortcut_key = crate::commands::shortcut::DEFAULT_SHORTCUT_KEY.to_string();
+            }
+            crate::whisper::set_models_dir_override(settings.models_dir.clone());
+            crate::whisper::set_model_mirror_override(settings.model_mirror_base.clone());
+            crate::whisper::set_threads_override(settings.whisper_threads);
+            crate::net::set_proxy_override(settings.http_proxy.clone());
+            if let Err(e) = crate::commands::audio::apply_audio_host(state, settings.audio_host.clone()) {
+                log::warn!("{}", e);
+            }
+            if let Err(e) = crate::commands::audio::apply_input_device(state, settings.input_device.clone()) {
+                log::warn!("{}", e);
+            }
+            if let Err(e) = crate::commands::audio::apply_pre_roll(state, settings.pre_roll_ms) {
+                log::warn!("{}", e);
+            }
+            if let Err(e) = crate::commands::audio::apply_trim_silence(state, settings.trim_silence_threshold) {
+                log::warn!("{}", e);
+            }
+            if let Err(e) = crate::commands::audio::apply_noise_gate(state, settings.noise_gate_threshold) {
+                log::warn!("{}", e);
+            }
             let mut inner = state.inner.lock().unwrap();
             inner.settings = settings;
             log::info!("已从磁盘加载持久化设置");
@@ -0,0 +1,181 @@
+// commands/shortcut.rs - 全局快捷键录音（切换 / 按住说话）
+//
+// 快捷键由 Rust 侧注册，按下 / 松开直接开始、停止录音并识别，
+// 通过事件通知前端，前端无需轮询：
+//   shortcut-recording-started  开始录音
+//   shortcut-recording-stopped  停止录音（payload 为 StopRecordingResponse），随后自动识别
+//   shortcut-recording-error    开始 / 停止失败（payload 为错误信息）
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+use crate::commands::audio::{begin_recording, finish_recording};
+use crate::state::{AppState, RecordingStatus, RecordingTriggerMode};
+
+/// 按住说话时快捷键是否仍处于按下状态
+/// 录音流启动较慢时可能在启动完成前就松开，启动完成后据此立即停止
+static KEY_HELD: AtomicBool = AtomicBool::new(false);
+
+/// 默认录音快捷键（旧版默认的单独 "Alt" 无法注册为全局快捷键）
+pub(crate) const DEFAULT_SHORTCUT_KEY: &str = "Alt+Space";
+
+/// 快捷键只有修饰键（如旧版默认的 "Alt"）时返回 true，这类组合无法注册为全局快捷键
+pub(crate) fn is_bare_modifier(shortcut_key: &str) -> bool {
+    let key = shortcut_key.trim();
+    !key.is_empty() && key.split('+').all(|part| matches!(
+        part.trim().to_ascii_lowercase().as_str(),
+        "alt" | "option" | "ctrl" | "control" | "shift" | "super" | "meta"
+            | "cmd" | "command" | "commandorcontrol" | "commandorctrl" | "cmdorctrl" | "cmdorcontrol"
+    ))
+}
+
+/// 注册录音快捷键（替换之前注册的快捷键）
+/// shortcut_key 格式如 "Alt+Space" / "CommandOrControl+Shift+D"；空字符串表示不注册
+pub(crate) fn apply_shortcut(app: &AppHandle, shortcut_key: &str) -> Result<(), String> {
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all()
+        .map_err(|e| format!("注销快捷键失败: {}", e))?;
+
+    let key = shortcut_key.trim();
+    if key.is_empty() {
+        return Ok(());
+    }
+    let shortcut: Shortcut = key.parse()
+        .map_err(|e| format!("快捷键 {} 无法注册为全局快捷键（需包含一个非修饰键，如 Alt+Space）: {}", key, e))?;
+    global_shortcut.register(shortcut)
+        .map_err(|e| format!("注册快捷键 {} 失败（可能已被其他应用占用）: {}", key, e))?;
+
+    log::info!("已注册录音快捷键: {}", key);
+    Ok(())
+}
+
+/// 全局快捷键回调（注册在 global-shortcut 插件上）
+/// 只注册了录音快捷键，不需要区分是哪个快捷键
+pub(crate) fn handle_shortcut(app: &AppHandle, _shortcut: &Shortcut, event: ShortcutEvent) {
+    let pressed = event.state() == ShortcutState::Pressed;
+    let mode = {
+        let state = app.state::<AppState>();
+        let Ok(inner) = state.inner.lock() else { return };
+        inner.settings.recording_trigger_mode.clone()
+    };
+
+    // 录音流启动会阻塞（打开设备），放到异步任务中，不占用事件循环
+    let app = app.clone();
+    match mode {
+        RecordingTriggerMode::Toggle if pressed => {
+            tauri::async_runtime::spawn(async move { toggle_recording(&app).await });
+        }
+        RecordingTriggerMode::PushToTalk => {
+            KEY_HELD.store(pressed, Ordering::SeqCst);
+            tauri::async_runtime::spawn(async move {
+                if pressed {
+                    push_to_talk_start(&app).await;
+                } else {
+                    stop_and_transcribe(&app).await;
+                }
+            });
+        }
+        RecordingTriggerMode::Toggle => {}
+    }
+}
+
+/// 切换模式：未录音时开始，录音中则停止并识别（识别中忽略）
+async fn toggle_recording(app: &AppHandle) {
+    let status = {
+        let state = app.state::<AppState>();
+        let Ok(inner) = state.inner.lock() else { return };
+        inner.recording_status.clone()
+    };
+    match status {
+        RecordingStatus::Idle => start(app),
+        RecordingStatus::Recording => stop_and_transcribe(app).await,
+        RecordingStatus::Processing => log::info!("正在识别中，忽略快捷键"),
+    }
+}
+
+/// 按住说话：按下时开始录音（按键重复触发时忽略）
+async fn push_to_talk_start(app: &AppHandle) {
+    let idle = {
+        let state = app.state::<AppState>();
+        let Ok(inner) = state.inner.lock() else { return };
+        inner.recording_status == RecordingStatus::Idle
+    };
+    if !idle || !start(app) {
+        return;
+    }
+
+    // 录音流启动期间已松开：松开时的停止请求因尚未进入录音状态而落空，这里补上
+    if !KEY_HELD.load(Ordering::SeqCst) {
+        log::info!("录音启动完成前快捷键已松开，立即停止");
+        stop_and_transcribe(app).await;
+    }
+}
+
+/// 开始录音并通知前端，返回是否成功
+fn start(app: &AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    match begin_recording(&state, app) {
+        Ok(()) => {
+            let _ = app.emit("shortcut-recording-started", ());
+            true
+        }
+        Err(e) => {
+            log::warn!("快捷键开始录音失败: {}", e);
+            let _ = app.emit("shortcut-recording-error", &e);
+            false
+        }
+    }
+}
+
+/// 停止录音并在后台识别（未在录音时忽略，如松开时录音尚未开始 / 已被自动停止）
+async fn stop_and_transcribe(app: &AppHandle) {
+    let resp = {
+        let state = app.state::<AppState>();
+        let recording = state.inner.lock()
+            .map(|inner| inner.recording_status == RecordingStatus::Recording)
+            .unwrap_or(false);
+        if !recording {
+            return;
+        }
+        match finish_recording(&state) {
+            Ok(resp) => resp,
+            Err(e) => {
+                log::warn!("快捷键停止录音失败: {}", e);
+                let _ = app.emit("shortcut-recording-error", &e);
+                return;
+            }
+        }
+    };
+
+    let _ = app.emit("shortcut-recording-stopped", &resp);
+    if resp.discarded {
+        return;
+    }
+    if let Err(e) = crate::commands::transcribe::transcribe_audio(
+        app.state::<AppState>(),
+        app.clone(),
+    ).await {
+        log::error!("快捷键停止后识别失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_modifiers_are_detected() {
+        assert!(is_bare_modifier("Alt"));
+        assert!(is_bare_modifier("Ctrl+Shift"));
+        assert!(is_bare_modifier(" CommandOrControl "));
+        assert!(!is_bare_modifier("Alt+Space"));
+        assert!(!is_bare_modifier("CommandOrControl+Shift+D"));
+        assert!(!is_bare_modifier(""));
+    }
+
+    #[test]
+    fn default_shortcut_is_registrable() {
+        assert!(!is_bare_modifier(DEFAULT_SHORTCUT_KEY));
+        assert!(DEFAULT_SHORTCUT_KEY.parse::<Shortcut>().is_ok());
+    }
+}
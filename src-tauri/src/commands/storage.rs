@@ -0,0 +1,193 @@
+// commands/storage.rs - 存储空间统计与清理命令
+
+use tauri::{Manager, State};
+use serde::Serialize;
+use std::path::Path;
+use crate::state::{AppState, ModelStatus};
+
+/// 最近这么多秒内修改过的 .part 文件视为仍在写入（如导入模型复制中），清理时跳过
+const PARTIAL_MIN_AGE_SECS: u64 = 60;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    pub models_dir: String,
+    /// 已下载 / 导入的模型文件（.bin）
+    pub models_bytes: u64,
+    /// 未完成的下载 / 导入残留（.part）
+    pub partial_download_bytes: u64,
+    /// 设置文件
+    pub settings_bytes: u64,
+    /// 历史记录文件（None = 尚未写入）
+    pub history_bytes: Option<u64>,
+    /// 日志文件（应用日志目录下的 .log 文件，None = 日志目录不可用）
+    pub log_bytes: Option<u64>,
+    /// 内存中保留的最近一次录音（供导出 WAV）
+    pub audio_cache_bytes: u64,
+}
+
+/// 统计各类数据占用的空间，供"管理存储"面板展示
+#[tauri::command]
+pub async fn get_storage_usage(
+    app:   tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<StorageUsage, String> {
+    let models_dir = crate::whisper::get_models_dir()
+        .map_err(|e| format!("获取模型目录失败: {}", e))?;
+
+    let (mut models_bytes, mut partial_download_bytes) = (0u64, 0u64);
+    if let Ok(entries) = std::fs::read_dir(&models_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if is_partial_file(&path) {
+                partial_download_bytes += size;
+            } else if path.extension().is_some_and(|ext| ext == "bin") {
+                models_bytes += size;
+            }
+        }
+    }
+
+    let settings_bytes = app.path().app_data_dir()
+        .ok()
+        .and_then(|dir| std::fs::metadata(dir.join(crate::commands::settings::STORE_FILE)).ok())
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    let history_bytes = crate::commands::history::history_path(&app)
+        .ok()
+        .and_then(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len());
+
+    let audio_cache_bytes = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.last_recording.as_ref()
+            .map(|r| (r.len() * std::mem::size_of::<f32>()) as u64)
+            .unwrap_or(0)
+    };
+
+    Ok(StorageUsage {
+        models_dir: models_dir.to_string_lossy().to_string(),
+        models_bytes,
+        partial_download_bytes,
+        settings_bytes,
+        history_bytes,
+        log_bytes: log_files(&app).map(|files| files.iter().map(|(_, size)| size).sum()),
+        audio_cache_bytes,
+    })
+}
+
+/// 按类别清理数据，返回释放的字节数
+///
+/// categories 可选值：
+/// - "partialDownloads"：模型目录中未完成的 .part 文件（有下载进行中时跳过）
+/// - "audioCache"：内存中保留的最近一次录音
+/// - "logs"：应用日志目录下的日志文件（当前日志清空，轮转出的旧日志删除）
+///
+/// 模型文件不在此清理，请使用 delete_model
+#[tauri::command]
+pub async fn clean_storage(
+    categories: Vec<String>,
+    app:        tauri::AppHandle,
+    state:      State<'_, AppState>,
+) -> Result<u64, String> {
+    let mut freed = 0u64;
+
+    for category in &categories {
+        match category.as_str() {
+            "partialDownloads" => freed += clean_partial_downloads(&state)?,
+            "audioCache" => {
+                let mut inner = state.inner.lock()
+                    .map_err(|e| format!("状态锁失败: {}", e))?;
+                if let Some(recording) = inner.last_recording.take() {
+                    freed += (recording.len() * std::mem::size_of::<f32>()) as u64;
+                }
+                inner.last_recording_id = None;
+            }
+            "logs" => freed += clean_logs(&app),
+            other => return Err(format!("未知的清理类别: {}", other)),
+        }
+    }
+
+    log::info!("存储清理完成（{:?}），释放 {} 字节", categories, freed);
+    Ok(freed)
+}
+
+/// 应用日志目录下的日志文件及大小（日志目录不可用时返回 None）
+fn log_files(app: &tauri::AppHandle) -> Option<Vec<(std::path::PathBuf, u64)>> {
+    let dir = app.path().app_log_dir().ok()?;
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Some(Vec::new()); // 尚未写入任何日志
+    };
+    Some(entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|path| {
+            let size = std::fs::metadata(&path).ok()?.len();
+            Some((path, size))
+        })
+        .collect())
+}
+
+/// 清理日志文件，返回释放的字节数
+/// 当前日志文件仍被日志插件打开，只清空内容；轮转出的旧日志直接删除
+fn clean_logs(app: &tauri::AppHandle) -> u64 {
+    let current = format!("{}.log", crate::LOG_FILE_NAME);
+    let mut freed = 0u64;
+    for (path, size) in log_files(app).unwrap_or_default() {
+        let is_current = path.file_name().is_some_and(|name| name == current.as_str());
+        let result = if is_current {
+            std::fs::OpenOptions::new().write(true).open(&path).and_then(|f| f.set_len(0))
+        } else {
+            std::fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => freed += size,
+            Err(e) => log::warn!("清理日志文件 {:?} 失败: {}", path, e),
+        }
+    }
+    freed
+}
+
+/// 删除模型目录中残留的 .part 文件
+fn clean_partial_downloads(state: &AppState) -> Result<u64, String> {
+    {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        if inner.downloads.values().any(|d| d.status == ModelStatus::Downloading) {
+            return Err("有模型正在下载，请等待下载完成后再清理".to_string());
+        }
+    }
+
+    let models_dir = crate::whisper::get_models_dir()
+        .map_err(|e| format!("获取模型目录失败: {}", e))?;
+    let entries = std::fs::read_dir(&models_dir)
+        .map_err(|e| format!("读取模型目录失败: {}", e))?;
+
+    let mut freed = 0u64;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !is_partial_file(&path) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        let recently_modified = meta.modified()
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age.as_secs() < PARTIAL_MIN_AGE_SECS);
+        if recently_modified {
+            log::info!("跳过仍在写入的文件: {:?}", path);
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => freed += meta.len(),
+            Err(e) => log::warn!("删除 {:?} 失败: {}", path, e),
+        }
+    }
+    Ok(freed)
+}
+
+fn is_partial_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "part")
+}
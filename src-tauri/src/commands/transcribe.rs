@@ -2,21 +2,30 @@
 
 use tauri::{State, Emitter};
 use serde::Serialize;
-use crate::state::{AppState, RecordingStatus, TranscriptionMode, ModelStatus, HistoryItem};
-use crate::cloud::{transcribe_cloud, CloudTranscribeParams};
+use crate::state::{AppState, AppSettings, RecordingStatus, TranscriptionMode, ModelStatus, HistoryItem, CloudProvider};
+use crate::cloud::{transcribe_cloud, effective_model_name, CloudAudio, CloudTranscribeParams};
 
-/// Whisper 推理超时时间（秒）
-/// Windows 启用 CUDA 后大模型也应在 10 秒内完成，120 秒是安全边界
+/// Whisper 推理超时下限（秒）
+/// 超时按音频时长 × 模型实时率估算，短音频也至少等待这么久
 /// 如果用户没有 NVIDIA 显卡 / 没装 CUDA 驱动，会自动回退 CPU，此时仍有超时保护
-const INFERENCE_TIMEOUT_SECS: u64 = 120;
+const MIN_INFERENCE_TIMEOUT_SECS: u64 = 30;
+
+/// 估算值之外的固定余量（秒）：创建 state、调度抖动等
+const INFERENCE_TIMEOUT_MARGIN_SECS: u64 = 20;
+
+/// 推理超时上限的默认值（秒），可在设置中修改
+pub const DEFAULT_INFERENCE_TIMEOUT_CAP_SECS: u64 = 600;
+
+/// 推理超时上限允许设置的最大值（秒）
+pub const MAX_INFERENCE_TIMEOUT_CAP_SECS: u64 = 3600;
 
 /// 推理线程栈大小：64MB
 /// whisper.cpp 使用大量局部变量/递归，Windows 默认 1MB 栈会导致闪退（栈溢出）
 /// 64MB 足够所有模型（包括 Large-v3）正常运行
-const INFERENCE_STACK_SIZE: usize = 64 * 1024 * 1024;
+pub(crate) const INFERENCE_STACK_SIZE: usize = 64 * 1024 * 1024;
 
-/// 模型加载线程栈大小：32MB
-const LOAD_STACK_SIZE: usize = 32 * 1024 * 1024;
+/// 分声道识别时低于此 RMS 的声道视为无人说话，直接跳过
+const SILENT_CHANNEL_RMS: f32 = 0.0005;
 
 // ===== 识别状态查询 =====
 
@@ -49,6 +58,41 @@ pub struct TranscribeResult {
     pub text: String,
     pub duration_ms: u64,
     pub item_id: String,
+    /// 自动翻译结果（开启 translate_on_complete 且翻译成功时）
+    pub translation: Option<String>,
+    /// 自动复制时应写入剪贴板的文本（有翻译时为译文）
+    pub clipboard_text: String,
+    /// 是否已自动复制到剪贴板（开启 auto_copy 且写入成功）
+    pub copied: bool,
+    /// 本次录音的音频统计，用于提示音量问题和排查反馈
+    pub audio_stats: AudioStats,
+    /// 分声道录音时各声道的识别结果（text 为按声道标注后的合并文本）
+    pub channels: Option<Vec<ChannelTranscript>>,
+}
+
+/// 录音音频统计（16kHz 单声道数据）
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioStats {
+    pub rms: f32,
+    /// 峰值（绝对值）
+    pub peak: f32,
+    pub duration_ms: u64,
+    pub sample_count: usize,
+    /// 采集时长与墙钟时长对比（排查"时间戳不准"：设备时钟漂移 / 回调丢帧）
+    pub capture_timing: Option<crate::audio::CaptureTiming>,
+}
+
+impl AudioStats {
+    fn from_samples(samples: &[f32], capture_timing: Option<crate::audio::CaptureTiming>) -> Self {
+        AudioStats {
+            rms: crate::whisper::audio_rms(samples),
+            peak: samples.iter().fold(0.0f32, |m, &s| m.max(s.abs())),
+            duration_ms: (samples.len() as f64 / 16000.0 * 1000.0) as u64,
+            sample_count: samples.len(),
+            capture_timing,
+        }
+    }
 }
 
 /// 执行语音识别
@@ -62,178 +106,236 @@ pub struct TranscribeResult {
 ///
 /// 重要规则：不能在持有 Mutex 锁的同时 .await
 /// 所以先拿数据、释放锁，再 await，再拿锁写结果
+///
+/// 每条退出路径都会通知前端：成功发送 new-transcription，
+/// 失败时（含无录音数据、凭据缺失等提前返回）若状态仍为 Processing，
+/// 发送 transcription-failed 并复位为 Idle，保证悬浮窗不会停在"识别中"
 #[tauri::command]
 pub async fn transcribe_audio(
     state: State<'_, AppState>,
     app: tauri::AppHandle,
 ) -> Result<TranscribeResult, String> {
+    // 统计上报需要的信息在识别前取出（识别会消耗缓冲区）
+    let metrics = state.inner.lock().ok().and_then(|inner| {
+        let endpoint = inner.settings.metrics_endpoint.as_deref()
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(str::to_string);
+        let audio_ms = inner.audio_buffer.as_ref()
+            .map(|b| (b.len() as f64 / 16000.0 * 1000.0) as u64)
+            .unwrap_or(0);
+        endpoint.map(|endpoint| (endpoint, TranscriptionMetrics::new(&inner.settings, audio_ms)))
+    });
+    let started = std::time::Instant::now();
 
-    // ── 第一步：把需要的数据从 inner 里取出来，然后立即释放锁 ──────────
-    let (settings, audio_data, duration_ms) = {
+    let result = run_transcribe_audio(state.clone(), app.clone()).await;
+    if let Err(e) = &result {
+        log::warn!("识别失败: {}", e);
+        // 只在仍处于 Processing 时复位并通知：重复调用（前端与快捷键 / 自动停止同时触发）
+        // 或连续听写已开始下一句时，状态属于另一段录音，不能强制改为 Idle，也不再重复重启
+        if reset_to_idle(&state) {
+            let _ = app.emit("transcription-failed", e);
+            // 连续听写：用户取消时退出连续模式，其他失败（含未识别到语音）照常开始下一句
+            if e == crate::whisper::INFERENCE_CANCELLED {
+                crate::commands::audio::stop_continuous(&state, &app);
+            } else {
+                crate::commands::audio::restart_if_continuous(&state, &app);
+            }
+        }
+    }
+
+    if let Some((endpoint, mut payload)) = metrics {
+        payload.inference_secs = started.elapsed().as_secs_f64();
+        payload.success = result.is_ok();
+        send_metrics(endpoint, payload);
+    }
+    result
+}
+
+/// 取消进行中的本地推理
+///
+/// 推理线程在 whisper.cpp 的 abort 回调中检查取消标记并尽快返回"已取消"，
+/// transcribe_audio 随之失败：状态复位为 Idle、丢弃录音缓冲区、发送 transcription-failed
+#[tauri::command]
+pub async fn cancel_transcription(state: State<'_, AppState>) -> Result<(), String> {
+    {
         let inner = state.inner.lock()
             .map_err(|e| format!("状态锁失败: {}", e))?;
-
-        let audio = inner.audio_buffer.clone().unwrap_or_default();
-        if audio.is_empty() {
-            return Err("没有录音数据，请先完成录音".to_string());
+        if inner.recording_status != RecordingStatus::Processing {
+            return Err("当前没有进行中的识别".to_string());
         }
+    }
+    state.inference_cancel.store(true, std::sync::atomic::Ordering::SeqCst);
+    log::info!("已请求取消本地推理");
+    Ok(())
+}
 
-        let dur = (audio.len() as f64 / 16000.0 * 1000.0) as u64;
-        (inner.settings.clone(), audio, dur)
-    }; // ← 锁释放，后面可以安全 .await
+/// 带时间戳的识别结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetailedTranscribeResult {
+    pub text: String,
+    pub segments: Vec<crate::whisper::Segment>,
+    pub language: Option<String>,
+    pub duration_ms: u64,
+    /// 录音对应的历史记录 ID
+    pub item_id: Option<String>,
+}
 
-    log::info!(
-        "开始识别：模式={:?}, 音频={}ms, 语言={}",
-        settings.mode, duration_ms, settings.language
-    );
+/// 对最近一次录音重新进行本地识别，返回带起止时间的段落（供前端显示同步字幕）
+///
+/// 使用保留的最近一次录音（与导出 WAV 相同），段落保存到对应的历史记录（供导出字幕）；
+/// 无论当前识别模式如何都使用本地模型，原有 transcribe_audio 流程不受影响
+#[tauri::command]
+pub async fn transcribe_audio_detailed(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<DetailedTranscribeResult, String> {
+    let (settings, audio_data, item_id) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        if inner.recording_status != RecordingStatus::Idle {
+            return Err("正在录音或识别中，请稍后再试".to_string());
+        }
+        let audio = inner.last_recording.clone()
+            .filter(|a| !a.is_empty())
+            .ok_or_else(|| "没有可识别的录音，请先完成一次录音".to_string())?;
+        (inner.settings.clone(), audio, inner.last_recording_id.clone())
+    };
 
-    // ── 第二步：执行识别（可能耗时很长，所以在锁外 await）─────────────
-    let result_text = match &settings.mode {
-        TranscriptionMode::Cloud => {
-            // 云端 API 调用
-            if settings.cloud_api_key.is_empty() {
-                return Err("云端模式需要配置 API Key，请到设置页面填写".to_string());
+    let duration_ms = (audio_data.len() as f64 / 16000.0 * 1000.0) as u64;
+    let (segments, language) = transcribe_local_segments(&state, &app, &settings, &audio_data).await?;
+
+    if let Some(id) = &item_id {
+        let updated = {
+            let mut inner = state.inner.lock()
+                .map_err(|e| format!("状态锁失败: {}", e))?;
+            match inner.history.iter_mut().find(|item| &item.id == id) {
+                Some(item) => {
+                    item.segments = Some(segments.clone());
+                    true
+                }
+                None => false,
             }
-            if settings.cloud_base_url.is_empty() {
-                return Err("云端模式需要配置 Base URL，请到设置页面填写".to_string());
+        };
+        if updated {
+            if let Err(e) = crate::commands::history::persist_history(&app, &state) {
+                log::warn!("保存段落时间戳失败: {}", e);
             }
-
-            let params = CloudTranscribeParams {
-                audio_samples: audio_data,
-                language: settings.language.clone(),
-                provider: settings.cloud_provider.clone(),
-                base_url: settings.cloud_base_url.clone(),
-                api_key: settings.cloud_api_key.clone(),
-            };
-
-            transcribe_cloud(params)
-                .await
-                .map_err(|e| format!("云端识别失败: {}", e))?
         }
+    }
 
-        TranscriptionMode::Local => {
-            // ── 本地 Whisper 推理 ──────────────────────────────────────────
+    Ok(DetailedTranscribeResult {
+        text: crate::whisper::join_segments(&segments),
+        segments,
+        language,
+        duration_ms,
+        item_id,
+    })
+}
+
+/// 匿名识别统计：只含耗时和分类字段，不含识别文本
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptionMetrics {
+    mode: TranscriptionMode,
+    /// 本地为模型名（如 "small"），云端为 服务商/模型
+    model: String,
+    backend: String,
+    audio_ms: u64,
+    inference_secs: f64,
+    success: bool,
+}
+
+impl TranscriptionMetrics {
+    fn new(settings: &AppSettings, audio_ms: u64) -> Self {
+        let (model, backend) = match settings.mode {
+            TranscriptionMode::Local => (
+                settings.local_model.clone(),
+                crate::whisper::compute_backend().to_string(),
+            ),
+            TranscriptionMode::Cloud => (cloud_model_label(settings), "cloud".to_string()),
+        };
+        TranscriptionMetrics {
+            mode: settings.mode.clone(),
+            model,
+            backend,
+            audio_ms,
+            inference_secs: 0.0,
+            success: false,
+        }
+    }
+}
 
-            // 1. 检查模型是否已下载
-            let model = crate::whisper::WhisperModel::from_str(&settings.local_model)
-                .ok_or_else(|| format!("未知模型 \"{}\"，请到设置页面重新选择", settings.local_model))?;
+/// 统计上报超时（秒）
+const METRICS_TIMEOUT_SECS: u64 = 5;
 
-            if !crate::whisper::is_model_downloaded(&model) {
-                return Err(format!(
-                    "模型 {} 尚未下载，请先到设置 → 本地模型 页面下载",
-                    model.display_name()
-                ));
+/// 后台发送统计，不等待结果，失败只记 debug 日志
+fn send_metrics(endpoint: String, payload: TranscriptionMetrics) {
+    tauri::async_runtime::spawn(async move {
+        let client = match crate::net::client_builder()
+            .timeout(std::time::Duration::from_secs(METRICS_TIMEOUT_SECS))
+            .build()
+        {
+            Ok(c) => c,
+            Err(e) => {
+                log::debug!("统计上报失败: {}", e);
+                return;
             }
+        };
+        if let Err(e) = client.post(&endpoint).json(&payload).send().await {
+            log::debug!("统计上报失败: {}", e);
+        }
+    });
+}
 
-            let model_path = crate::whisper::get_model_path(&model)
-                .map_err(|e| format!("获取模型路径失败: {}", e))?;
+async fn run_transcribe_audio(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<TranscribeResult, String> {
 
-            // 2. 判断是否需要（重新）加载模型
-            //    同一个模型已加载则跳过，换了模型才重新加载
-            let needs_load = {
-                let eng = state.whisper.lock()
-                    .map_err(|e| format!("引擎锁失败: {}", e))?;
-                eng.current_model_name().map(|s| s.to_string())
-                    != Some(model.filename().to_string())
-            };
-
-            if needs_load {
-                // 通知前端：正在加载模型
-                {
-                    let mut inner = state.inner.lock()
-                        .map_err(|e| format!("状态锁失败: {}", e))?;
-                    inner.model_status = ModelStatus::Loading;
-                }
+    // ── 第一步：把需要的数据从 inner 里取出来，然后立即释放锁 ──────────
+    let (settings, audio_data, channel_data, capture_timing, duration_ms) = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
 
-                log::info!("加载 Whisper 模型: {}", model.display_name());
-
-                // 模型加载：使用大栈线程（避免 Windows 1MB 默认栈溢出）
-                let whisper_arc = state.whisper.clone();
-                let (load_tx, load_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
-                std::thread::Builder::new()
-                    .name("whisper-model-load".to_string())
-                    .stack_size(LOAD_STACK_SIZE)
-                    .spawn(move || {
-                        let result = (|| -> Result<(), String> {
-                            let mut eng = whisper_arc.lock()
-                                .map_err(|e| format!("引擎锁失败: {}", e))?;
-                            eng.load_model(&model_path)
-                                .map_err(|e| format!("加载模型失败: {}", e))
-                        })();
-                        let _ = load_tx.send(result);
-                    })
-                    .map_err(|e| format!("创建加载线程失败: {}", e))?;
+        let audio = inner.audio_buffer.clone().unwrap_or_default();
+        if audio.is_empty() {
+            return Err("没有录音数据，请先完成录音".to_string());
+        }
 
-                load_rx.await
-                    .map_err(|e| format!("加载线程通信失败: {}", e))
-                    .and_then(|r| r)?;
+        let dur = (audio.len() as f64 / 16000.0 * 1000.0) as u64;
+        (inner.settings.clone(), audio, inner.channel_buffers.clone(), inner.capture_timing.clone(), dur)
+    }; // ← 锁释放，后面可以安全 .await
 
-                // 加载完成，更新状态
-                {
-                    let mut inner = state.inner.lock()
-                        .map_err(|e| format!("状态锁失败: {}", e))?;
-                    inner.model_status = ModelStatus::Ready;
-                }
+    // 音频统计在数据交给识别流程前计算（本地/云端路径都会消耗 audio_data）
+    let audio_stats = AudioStats::from_samples(&audio_data, capture_timing);
 
-                log::info!("模型加载完成: {}", model.display_name());
-            }
+    log::info!(
+        "开始识别：模式={:?}, 音频={}ms, 语言={}",
+        settings.mode, duration_ms, settings.language
+    );
 
-            // 3. 执行推理（同样是 blocking，放入专用线程）
-            //    添加超时保护：Windows CPU 推理可能非常慢
-            log::info!(
-                "开始本地 Whisper 推理，语言: {}, 超时: {}秒",
-                settings.language, INFERENCE_TIMEOUT_SECS
-            );
+    // ── 第二步：执行识别（可能耗时很长，所以在锁外 await）─────────────
+    // 分声道录音时每个声道单独识别，结果按声道标注后合并
+    let (run, channels) = match channel_data {
+        Some(channel_data) => {
+            let (run, channels) = transcribe_channels(&state, &app, &settings, channel_data, audio_data).await?;
+            (run, Some(channels))
+        }
+        None => (run_transcription(&state, &app, &settings, audio_data).await?, None),
+    };
+    let TranscriptionRun { text: result_text, mode: used_mode, is_fallback, model_name, language } = run;
+    // 本地 whisper 翻译模式输出的是英文译文（云端回退的结果仍是原语言）
+    let is_translation = settings.translate_to_english && used_mode == TranscriptionMode::Local;
 
-            let whisper_arc = state.whisper.clone();
-            let audio_clone = audio_data.clone();
-            let lang_clone  = settings.language.clone();
-
-            // 使用 64MB 大栈线程 + oneshot channel：
-            // whisper.cpp 推理在 Windows 上需要大量栈空间，
-            // 默认 1MB 栈会导致栈溢出闪退（即使是 Tiny 模型）
-            let (infer_tx, infer_rx) = tokio::sync::oneshot::channel::<Result<String, String>>();
-            std::thread::Builder::new()
-                .name("whisper-inference".to_string())
-                .stack_size(INFERENCE_STACK_SIZE)
-                .spawn(move || {
-                    let result = (|| -> Result<String, String> {
-                        let eng = whisper_arc.lock()
-                            .map_err(|e| format!("引擎锁失败: {}", e))?;
-                        eng.transcribe(&audio_clone, &lang_clone)
-                            .map_err(|e| format!("本地识别失败: {}", e))
-                    })();
-                    let _ = infer_tx.send(result);
-                })
-                .map_err(|e| format!("创建推理线程失败: {}", e))?;
-
-            // 等待推理完成，带超时保护
-            let timeout_duration = std::time::Duration::from_secs(INFERENCE_TIMEOUT_SECS);
-            match tokio::time::timeout(timeout_duration, infer_rx).await {
-                Ok(Ok(result)) => result?,
-                Ok(Err(e)) => return Err(format!("推理线程通信失败: {}", e)),
-                Err(_elapsed) => {
-                    log::error!(
-                        "Whisper 推理超时（{}秒），放弃等待",
-                        INFERENCE_TIMEOUT_SECS
-                    );
-                    {
-                        let mut inner = state.inner.lock()
-                            .map_err(|e| format!("状态锁失败: {}", e))?;
-                        inner.recording_status = RecordingStatus::Idle;
-                        inner.audio_buffer = None;
-                    }
-                    return Err(format!(
-                        "本地识别超时（已等待 {} 秒）。\n\
-                         建议：\n\
-                         1. 使用更小的模型（如 Tiny 或 Base）\n\
-                         2. 缩短录音时长\n\
-                         3. 或切换到云端识别模式",
-                        INFERENCE_TIMEOUT_SECS
-                    ));
-                }
-            }
+    // ── 可选：识别完成后自动翻译（失败不影响识别结果）──────────────────
+    let translation = match settings.translate_on_complete.as_deref() {
+        Some(to) if !result_text.is_empty() => {
+            let from = if is_translation { Some("en") } else { language.as_deref() };
+            translate_result(&state, &app, &result_text, from, to).await
         }
+        _ => None,
     };
 
     // ── 第三步：把结果写回 inner，更新历史 ──────────────────────────────
@@ -243,8 +345,14 @@ pub async fn transcribe_audio(
         text: result_text.clone(),
         timestamp: chrono::Utc::now(),
         duration_ms,
-        mode: settings.mode.clone(),
-        model_name: None,
+        mode: used_mode,
+        model_name,
+        language,
+        is_fallback,
+        translation: translation.clone(),
+        segments: None,
+        is_translation,
+        pinned: false,
     };
 
     {
@@ -253,38 +361,642 @@ pub async fn transcribe_audio(
 
         inner.history.insert(0, item.clone()); // 最新的排最前
 
-        // 超出上限则截断
+        // 超出上限则删除最旧的未固定记录
         let max = inner.settings.max_history;
-        inner.history.truncate(max);
+        crate::commands::history::truncate_history(&mut inner.history, max);
 
-        // 清空缓冲区，状态回 Idle
-        inner.audio_buffer = None;
+        // 清空缓冲区（保留最近一次录音供导出），状态回 Idle
+        inner.last_recording = inner.audio_buffer.take();
+        inner.last_recording_id = Some(item_id.clone());
+        if item.mode == TranscriptionMode::Local {
+            inner.model_last_used = Some(std::time::Instant::now());
+        }
+        inner.channel_buffers = None;
+        inner.capture_timing = None;
         inner.recording_status = RecordingStatus::Idle;
+        inner.processing_since = None;
+    }
+
+    // 立即写盘，崩溃时最多丢失正在识别的这一条
+    if let Err(e) = crate::commands::history::persist_history(&app, &state) {
+        log::error!("历史记录写盘失败: {}", e);
     }
 
     // ── 第四步：通知前端 ─────────────────────────────────────────────────
     // emit 是 Tauri 的事件广播，前端通过 listen('new-transcription', ...) 接收
     let _ = app.emit("new-transcription", &item);
 
+    // 悬浮窗隐藏时用系统通知告知识别完成
+    if settings.notify_on_complete {
+        notify_if_hidden(&app, &item.text);
+    }
+
+    let copy_text = translation.as_deref().unwrap_or(&result_text);
+    let copy_text = if settings.insert_timestamp_prefix {
+        with_timestamp_prefix(copy_text, &item.timestamp, &settings.timestamp_prefix_format)
+    } else {
+        copy_text.to_string()
+    };
+    let clipboard_text = with_copy_suffix(&copy_text, &settings.auto_copy_suffix);
+
+    // 自动复制在后端完成：快捷键、静音自动停止、时长上限、连续听写等触发方式都不经过前端
+    let copied = settings.auto_copy && !clipboard_text.is_empty() && {
+        match crate::commands::clipboard::write_clipboard(&app, &state, clipboard_text.clone()) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("自动复制识别结果失败: {}", e);
+                false
+            }
+        }
+    };
+
+    // 连续听写：自动开始下一句
+    crate::commands::audio::restart_if_continuous(&state, &app);
+
     Ok(TranscribeResult {
         text: result_text,
         duration_ms,
         item_id,
+        translation,
+        clipboard_text,
+        copied,
+        audio_stats,
+        channels,
     })
 }
 
+// ── 识别实现 ────────────────────────────────────────────────────────────────
+
+/// 一次识别的结果及元数据
+/// mode：实际完成识别的方式（本地失败回退云端时为 Cloud）
+/// model_name / language：写入历史记录的元数据
+struct TranscriptionRun {
+    text: String,
+    mode: TranscriptionMode,
+    is_fallback: bool,
+    model_name: Option<String>,
+    language: Option<String>,
+}
+
+/// 按 settings.mode 对一段音频执行识别（含本地失败回退云端）
+async fn run_transcription(
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
+    settings: &AppSettings,
+    audio_data: Vec<f32>,
+) -> Result<TranscriptionRun, String> {
+    match &settings.mode {
+        TranscriptionMode::Cloud => {
+            // 服务商与语言不匹配时提示（只提示，不阻止识别）
+            for warning in config_warnings(settings) {
+                log::warn!("配置提示: {}", warning);
+                let _ = app.emit("config-warning", &warning);
+            }
+            let text = transcribe_with_cloud(settings, audio_data).await?;
+            Ok(TranscriptionRun {
+                text,
+                mode: TranscriptionMode::Cloud,
+                is_fallback: false,
+                model_name: Some(cloud_model_label(settings)),
+                language: configured_language(settings),
+            })
+        }
+
+        TranscriptionMode::Local => {
+            let local_result = transcribe_local(state, app, settings, &audio_data).await;
+            match local_result {
+                Ok((text, language)) => {
+                    let model_name = local_model(settings).map(|m| m.filename().to_string());
+                    Ok(TranscriptionRun {
+                        text,
+                        mode: TranscriptionMode::Local,
+                        is_fallback: false,
+                        model_name,
+                        language,
+                    })
+                }
+
+                // 本地失败/超时 + 开启了云端回退 + 云端凭据完整 → 用同一段音频重试云端
+                // 用户主动取消时不回退
+                Err(local_err) if settings.fallback_to_cloud
+                    && local_err != crate::whisper::INFERENCE_CANCELLED
+                    && check_cloud_credentials(settings).is_ok() => {
+                    log::warn!("本地识别失败，回退到云端识别: {}", local_err);
+                    let _ = app.emit("transcription-fallback", &local_err);
+
+                    let text = transcribe_with_cloud(settings, audio_data)
+                        .await
+                        .map_err(|cloud_err| format!("{}\n云端回退也失败: {}", local_err, cloud_err))?;
+                    Ok(TranscriptionRun {
+                        text,
+                        mode: TranscriptionMode::Cloud,
+                        is_fallback: true,
+                        model_name: Some(cloud_model_label(settings)),
+                        language: configured_language(settings),
+                    })
+                }
+
+                Err(e) => Err(e),
+            }
+        }
+    }
+}
+
+/// 单个声道的识别结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelTranscript {
+    /// 声道序号（从 0 开始）
+    pub channel: usize,
+    /// 显示标签（左声道 / 右声道 / 声道 N）
+    pub label: String,
+    pub text: String,
+}
+
+/// 分声道识别：逐个声道识别，静音声道跳过，合并文本按声道标注
+/// 所有声道都静音时退回单声道混音识别（由其给出音量过低的错误提示）
+async fn transcribe_channels(
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
+    settings: &AppSettings,
+    channel_data: Vec<Vec<f32>>,
+    mono: Vec<f32>,
+) -> Result<(TranscriptionRun, Vec<ChannelTranscript>), String> {
+    let total = channel_data.len();
+    let mut transcripts = Vec::new();
+    let mut first_run: Option<TranscriptionRun> = None;
+
+    for (channel, samples) in channel_data.into_iter().enumerate() {
+        if crate::whisper::audio_rms(&samples) < SILENT_CHANNEL_RMS {
+            log::info!("声道 {} 基本无声，跳过", channel);
+            continue;
+        }
+        let run = run_transcription(state, app, settings, samples).await?;
+        transcripts.push(ChannelTranscript {
+            channel,
+            label: channel_label(channel, total),
+            text: run.text.clone(),
+        });
+        first_run.get_or_insert(run);
+    }
+
+    let Some(mut run) = first_run else {
+        return Ok((run_transcription(state, app, settings, mono).await?, Vec::new()));
+    };
+
+    run.text = transcripts.iter()
+        .filter(|t| !t.text.is_empty())
+        .map(|t| format!("[{}] {}", t.label, t.text))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok((run, transcripts))
+}
+
+/// 声道显示标签：双声道为左 / 右，更多声道按序号
+fn channel_label(channel: usize, total: usize) -> String {
+    match (total, channel) {
+        (2, 0) => "左声道".to_string(),
+        (2, 1) => "右声道".to_string(),
+        _      => format!("声道 {}", channel + 1),
+    }
+}
+
+/// 检查云端凭据是否完整
+pub(crate) fn check_cloud_credentials(settings: &AppSettings) -> Result<(), String> {
+    if settings.cloud_api_key.is_empty() {
+        return Err("云端模式需要配置 API Key，请到设置页面填写".to_string());
+    }
+    if settings.cloud_base_url.is_empty() && crate::cloud::default_base_url(&settings.cloud_provider).is_none() {
+        if settings.cloud_provider == CloudProvider::Azure {
+            return Err("Azure 语音服务需要配置区域，请到设置页面填写".to_string());
+        }
+        return Err("云端模式需要配置 Base URL，请到设置页面填写".to_string());
+    }
+    Ok(())
+}
+
+/// 云端识别：校验凭据后调用对应服务商
+async fn transcribe_with_cloud(settings: &AppSettings, audio_data: Vec<f32>) -> Result<String, String> {
+    check_cloud_credentials(settings)?;
+
+    let params = CloudTranscribeParams {
+        audio: CloudAudio::Pcm(audio_data),
+        language: settings.language.clone(),
+        provider: settings.cloud_provider.clone(),
+        base_url: settings.cloud_base_url.clone(),
+        api_key: settings.cloud_api_key.clone(),
+        model: settings.cloud_model.clone(),
+        response_format: settings.cloud_response_format.clone(),
+        max_upload_bytes: settings.cloud_max_upload_mb * 1024 * 1024,
+        max_retries: settings.cloud_max_retries,
+    };
+
+    transcribe_cloud(params)
+        .await
+        .map_err(|e| format!("云端识别失败: {}", e))
+}
+
+/// 云端识别写入历史的模型标识：服务商/模型
+fn cloud_model_label(settings: &AppSettings) -> String {
+    format!(
+        "{:?}/{}",
+        settings.cloud_provider,
+        effective_model_name(&settings.cloud_provider, settings.cloud_model.as_deref())
+    )
+}
+
+/// 检查当前识别配置，返回建议性提示（不影响识别）
+#[tauri::command]
+pub async fn check_config(
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let settings = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.settings.clone()
+    };
+    Ok(config_warnings(&settings))
+}
+
+/// 语言搭配提示：本地模式检查模型，云端模式检查服务商
+fn config_warnings(settings: &AppSettings) -> Vec<String> {
+    match settings.mode {
+        TranscriptionMode::Local => local_model(settings)
+            .and_then(|model| crate::whisper::language_model_advice(&settings.language, &model)),
+        TranscriptionMode::Cloud => crate::cloud::provider_language_advice(&settings.cloud_provider, &settings.language),
+    }
+    .into_iter()
+    .collect()
+}
+
+/// 查询云端服务商是否支持某识别语言（仅供提示，不支持时识别仍可进行）
+///
+/// provider 与 CloudProvider 的 serde 名称一致（如 "aliyun"），无法识别的按自定义服务处理
+#[tauri::command]
+pub async fn provider_supports_language(
+    provider: String,
+    language: String,
+) -> Result<bool, String> {
+    let cloud_provider: CloudProvider = serde_json::from_value(serde_json::Value::String(provider))
+        .unwrap_or(CloudProvider::Custom);
+    Ok(crate::cloud::provider_supports_language(&cloud_provider, &language))
+}
+
+/// 本地识别实际使用的模型（英语 + prefer_english_models 时可能是 .en 模型）
+fn local_model(settings: &AppSettings) -> Option<crate::whisper::WhisperModel> {
+    crate::whisper::resolve_local_model(
+        &settings.local_model,
+        &settings.language,
+        settings.prefer_english_models,
+    )
+}
+
+/// 用户指定的识别语言（auto 时为 None，云端不返回检测结果）
+fn configured_language(settings: &AppSettings) -> Option<String> {
+    match settings.language.as_str() {
+        "" | "auto" => None,
+        lang => Some(lang.to_string()),
+    }
+}
+
+/// 本地 Whisper 识别：按需加载模型，在大栈线程中推理，带超时保护
+/// 返回 (识别文本, 实际语言)
+async fn transcribe_local(
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
+    settings: &AppSettings,
+    audio_data: &[f32],
+) -> Result<(String, Option<String>), String> {
+    let (segments, language) = transcribe_local_segments(state, app, settings, audio_data).await?;
+    Ok((crate::whisper::join_segments(&segments), language))
+}
+
+/// transcribe_local 的实际实现：返回带起止时间的段落和实际语言
+async fn transcribe_local_segments(
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
+    settings: &AppSettings,
+    audio_data: &[f32],
+) -> Result<(Vec<crate::whisper::Segment>, Option<String>), String> {
+    // 1. 检查模型是否已下载
+    let model = local_model(settings)
+        .ok_or_else(|| format!("未知模型 \"{}\"，请到设置页面重新选择", settings.local_model))?;
+
+    // 仅英语模型无法识别其他语言；自动检测时固定为英语
+    let language = match settings.language.as_str() {
+        "" | "auto" if model.is_english_only() => "en".to_string(),
+        lang if model.is_english_only() && lang != "en" => {
+            return Err(format!(
+                "{} 仅支持英语，请换用多语言模型或把识别语言改为英语",
+                model.display_name()
+            ));
+        }
+        lang => lang.to_string(),
+    };
+
+    if !crate::whisper::is_model_downloaded(&model) {
+        return Err(format!(
+            "模型 {} 尚未下载，请先到设置 → 本地模型 页面下载",
+            model.display_name()
+        ));
+    }
+
+    let model_path = crate::whisper::get_model_path(&model)
+        .map_err(|e| format!("获取模型路径失败: {}", e))?;
+
+    // 2. 判断是否需要（重新）加载模型
+    //    同一个模型已加载则跳过，换了模型才重新加载
+    let needs_load = {
+        let eng = state.whisper.lock()
+            .map_err(|e| format!("引擎锁失败: {}", e))?;
+        eng.current_model_name().map(|s| s.to_string())
+            != Some(model.filename().to_string())
+    };
+
+    if needs_load {
+        // 通知前端：正在加载模型
+        {
+            let mut inner = state.inner.lock()
+                .map_err(|e| format!("状态锁失败: {}", e))?;
+            inner.model_status = ModelStatus::Loading;
+        }
+
+        log::info!("加载 Whisper 模型: {}", model.display_name());
+
+        // 模型加载：使用大栈线程（避免 Windows 1MB 默认栈溢出），期间发送进度事件
+        // 引擎会先释放旧模型再加载新模型，失败时内存中已无模型，状态置为 Error 而不是停在 Loading
+        if let Err(e) = crate::commands::model::load_model_with_progress(app, state, &model, model_path).await {
+            let mut inner = state.inner.lock()
+                .map_err(|e| format!("状态锁失败: {}", e))?;
+            inner.model_status = ModelStatus::Error(e.clone());
+            return Err(e);
+        }
+
+        // 加载完成，更新状态
+        {
+            let mut inner = state.inner.lock()
+                .map_err(|e| format!("状态锁失败: {}", e))?;
+            inner.model_status = ModelStatus::Ready;
+            inner.model_last_used = Some(std::time::Instant::now());
+        }
+
+        log::info!("模型加载完成: {}", model.display_name());
+
+        // 换模型时检查语言搭配（只提示，不阻止识别）
+        for warning in config_warnings(settings) {
+            log::warn!("配置提示: {}", warning);
+            let _ = app.emit("config-warning", &warning);
+        }
+    }
+
+    // 3. 执行推理（同样是 blocking，放入专用线程）
+    //    添加超时保护：Windows CPU 推理可能非常慢，超时随音频时长和模型大小调整
+    let audio_duration_s = audio_data.len() as f64 / 16000.0;
+    let timeout_secs = inference_timeout_secs(&model, audio_duration_s, settings.inference_timeout_cap_secs);
+    match timeout_secs {
+        Some(secs) => log::info!("开始本地 Whisper 推理，语言: {}, 超时: {}秒", language, secs),
+        None => log::info!("开始本地 Whisper 推理，语言: {}, 不限制超时", language),
+    }
+
+    // 每次推理前清除上一次的取消标记
+    let cancel_flag = state.inference_cancel.clone();
+    cancel_flag.store(false, std::sync::atomic::Ordering::SeqCst);
+
+    let whisper_arc = state.whisper.clone();
+    let audio_clone = audio_data.to_vec();
+    let lang_clone  = language;
+    let segments    = crate::whisper::SegmentOptions {
+        max_len: settings.max_segment_len,
+        force_single_segment: settings.force_single_segment,
+        initial_prompt: settings.initial_prompt.clone(),
+        translate: settings.translate_to_english,
+    };
+
+    // 部分结果：推理线程每解码出一个段落就发到通道，由异步任务转发为 transcription-partial 事件
+    // 推理结束后回调随 whisper 参数一起释放，通道关闭，转发任务随之退出
+    let (partial_tx, mut partial_rx) = tokio::sync::mpsc::unbounded_channel::<crate::whisper::Segment>();
+    let partial_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(segment) = partial_rx.recv().await {
+            let _ = partial_app.emit("transcription-partial", &segment);
+        }
+    });
+    let on_segment: crate::whisper::SegmentCallback = Box::new(move |segment| {
+        let _ = partial_tx.send(segment);
+    });
+
+    // 使用 64MB 大栈线程 + oneshot channel：
+    // whisper.cpp 推理在 Windows 上需要大量栈空间，
+    // 默认 1MB 栈会导致栈溢出闪退（即使是 Tiny 模型）
+    type InferResult = Result<(Vec<crate::whisper::Segment>, Option<String>), String>;
+    let (infer_tx, infer_rx) = tokio::sync::oneshot::channel::<InferResult>();
+    std::thread::Builder::new()
+        .name("whisper-inference".to_string())
+        .stack_size(INFERENCE_STACK_SIZE)
+        .spawn(move || {
+            // catch_unwind：线程内 panic 转为命令错误，而不是只表现为通道断开
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| -> InferResult {
+                let eng = whisper_arc.lock()
+                    .map_err(|e| format!("引擎锁失败: {}", e))?;
+                eng.transcribe_with_segments(&audio_clone, &lang_clone, &segments, Some(cancel_flag), Some(on_segment))
+                    .map_err(|e| {
+                        let msg = e.to_string();
+                        if msg == crate::whisper::INFERENCE_CANCELLED {
+                            msg
+                        } else {
+                            format!("本地识别失败: {}", e)
+                        }
+                    })
+            }))
+            .unwrap_or_else(|payload| Err(format!(
+                "推理线程崩溃: {}", crate::panic_message(payload.as_ref())
+            )));
+            let _ = infer_tx.send(result);
+        })
+        .map_err(|e| format!("创建推理线程失败: {}", e))?;
+
+    // 等待推理完成，带超时保护（超时上限设为 0 时一直等待，可用 cancel_transcription 中止）
+    let Some(timeout_secs) = timeout_secs else {
+        return infer_rx.await
+            .unwrap_or_else(|e| Err(format!("推理线程通信失败: {}", e)));
+    };
+    let timeout_duration = std::time::Duration::from_secs(timeout_secs);
+    match tokio::time::timeout(timeout_duration, infer_rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(format!("推理线程通信失败: {}", e)),
+        Err(_elapsed) => {
+            log::error!(
+                "Whisper 推理超时（{}秒），放弃等待",
+                timeout_secs
+            );
+            Err(format!(
+                "本地识别超时（已等待 {} 秒）。\n\
+                 建议：\n\
+                 1. 使用更小的模型（如 Tiny 或 Base）\n\
+                 2. 缩短录音时长\n\
+                 3. 或切换到云端识别模式",
+                timeout_secs
+            ))
+        }
+    }
+}
+
+/// 估算推理超时（秒）：音频时长 × 模型 CPU 实时率 + 固定余量，限制在 [下限, cap] 内
+/// cap 为 0 表示不限制超时，返回 None
+pub(crate) fn inference_timeout_secs(model: &crate::whisper::WhisperModel, audio_duration_s: f64, cap_secs: u64) -> Option<u64> {
+    if cap_secs == 0 {
+        return None;
+    }
+    let estimate = (audio_duration_s * model.cpu_realtime_factor()).ceil() as u64
+        + INFERENCE_TIMEOUT_MARGIN_SECS;
+    let cap = cap_secs.max(MIN_INFERENCE_TIMEOUT_SECS);
+    Some(estimate.clamp(MIN_INFERENCE_TIMEOUT_SECS, cap))
+}
+
+/// 识别完成后的自动翻译
+///
+/// 需要调用 MyMemory 且今日额度已用完时直接跳过，不再发起请求；
+/// 失败时发送 translation-failed 事件并返回 None，识别结果照常写入
+async fn translate_result(
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
+    text: &str,
+    language: Option<&str>,
+    to: &str,
+) -> Option<String> {
+    use crate::commands::translate::{daily_quota_exhausted, detect_source_lang, is_local_conversion, translate};
+
+    let from = detect_source_lang(text, language);
+    if from == to {
+        return None;
+    }
+
+    if !is_local_conversion(from, to) {
+        let exhausted = state.inner.lock()
+            .map(|inner| daily_quota_exhausted(&inner))
+            .unwrap_or(false);
+        if exhausted {
+            log::warn!("今日翻译额度已用完，跳过自动翻译");
+            let _ = app.emit("translation-failed", "今日翻译次数已用完，已跳过自动翻译");
+            return None;
+        }
+    }
+
+    match translate(state, app, text.to_string(), from, to).await {
+        Ok(translated) => {
+            log::info!("自动翻译完成 ({} → {})", from, to);
+            Some(translated)
+        }
+        Err(e) => {
+            log::warn!("自动翻译失败: {}", e);
+            let _ = app.emit("translation-failed", &e);
+            None
+        }
+    }
+}
+
+/// 按 auto_copy_suffix 设置给剪贴板文本追加空格 / 换行（空文本不追加）
+fn with_copy_suffix(text: &str, suffix: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    match suffix {
+        "space"   => format!("{} ", text),
+        "newline" => format!("{}\n", text),
+        _         => text.to_string(),
+    }
+}
+
+/// 在剪贴板文本前加上识别时间（转换为本地时区后按 format 格式化，空文本不加）
+/// 格式串无效时原样返回文本，避免 chrono 格式化时 panic
+fn with_timestamp_prefix(text: &str, timestamp: &chrono::DateTime<chrono::Utc>, format: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    let Some(items) = parse_timestamp_format(format) else {
+        log::warn!("时间前缀格式无效，已忽略: {}", format);
+        return text.to_string();
+    };
+    let local = timestamp.with_timezone(&chrono::Local);
+    format!("{}{}", local.format_with_items(items.iter()), text)
+}
+
+/// 解析 strftime 格式串，含无效占位符时返回 None
+pub(crate) fn parse_timestamp_format(format: &str) -> Option<Vec<chrono::format::Item<'_>>> {
+    use chrono::format::{Item, StrftimeItems};
+
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        None
+    } else {
+        Some(items)
+    }
+}
+
+/// 主窗口不在前台（隐藏、最小化或被其他窗口挡住失去焦点）时弹出识别完成通知，
+/// 窗口可见且有焦点时不打扰
+fn notify_if_hidden(app: &tauri::AppHandle, text: &str) {
+    use tauri::Manager;
+    use tauri_plugin_notification::NotificationExt;
+
+    let in_foreground = app.get_webview_window("main")
+        .map(|w| {
+            w.is_visible().unwrap_or(false)
+                && !w.is_minimized().unwrap_or(false)
+                && w.is_focused().unwrap_or(false)
+        })
+        .unwrap_or(false);
+    if in_foreground {
+        return;
+    }
+
+    let mut preview = crate::truncate_chars(text, 60).to_string();
+    if preview.len() < text.len() {
+        preview.push('…');
+    }
+
+    if let Err(e) = app.notification()
+        .builder()
+        .title("Voxie 识别完成")
+        .body(preview)
+        .show()
+    {
+        log::warn!("发送识别完成通知失败: {}", e);
+    }
+}
+
+/// 识别失败时把状态复位为 Idle，丢弃缓冲区
+///
+/// 只在状态仍为 Processing 时复位，返回是否复位；
+/// 其他状态（如已开始新的录音）说明缓冲区和状态属于另一段录音，保持不动
+fn reset_to_idle(state: &State<'_, AppState>) -> bool {
+    let Ok(mut inner) = state.inner.lock() else { return false };
+    if inner.recording_status != RecordingStatus::Processing {
+        return false;
+    }
+    inner.recording_status = RecordingStatus::Idle;
+    inner.audio_buffer = None;
+    inner.channel_buffers = None;
+    inner.capture_timing = None;
+    inner.processing_since = None;
+    true
+}
+
 // ===== 测试云端连接 =====
 
 /// 测试云端 API 是否可用
 ///
-/// 根据 provider 分两条路：
-/// - "aliyun" → 调 NLS RESTful 接口（空 body 探测）
-/// - 其他      → 调 GET /models（OpenAI 兼容）
+/// 根据 provider 分四条路：
+/// - "aliyun"   → 调 NLS RESTful 接口（空 body 探测）
+/// - "deepgram" → 调 GET /projects 验证 API Key
+/// - "azure"    → 用密钥换取访问令牌（issueToken）
+/// - 其他       → 调 GET /models（OpenAI 兼容），并检查实际使用的模型是否在列表中
 #[tauri::command]
 pub async fn test_cloud_connection(
     base_url: String,
     api_key: String,
     provider: String,      // 前端传入，如 "aliyun" / "openAI" / ...
+    model: Option<String>, // 模型名覆盖（留空 = 服务商默认）
 ) -> Result<String, String> {
     use std::time::Duration;
 
@@ -292,8 +1004,16 @@ pub async fn test_cloud_connection(
     if provider == "aliyun" {
         return crate::cloud::test_aliyun_nls(&base_url, &api_key).await;
     }
+    // Deepgram 不是 OpenAI 兼容接口，单独验证 API Key
+    if provider == "deepgram" {
+        return crate::cloud::test_deepgram(&base_url, &api_key).await;
+    }
+    // Azure：base_url 为区域，用密钥换取令牌验证
+    if provider == "azure" {
+        return crate::cloud::test_azure(&base_url, &api_key).await;
+    }
 
-    // === OpenAI 兼容服务：GET /models ===
+    // === OpenAI 兼容服务（OpenAI / Groq / 自定义等）：GET /models ===
     if base_url.is_empty() {
         return Err("请先填写 Base URL".to_string());
     }
@@ -304,7 +1024,7 @@ pub async fn test_cloud_connection(
     let url = format!("{}/models", base_url.trim_end_matches('/'));
     log::info!("测试云端连接 ({}): {}", provider, url);
 
-    let client = reqwest::Client::builder()
+    let client = crate::net::client_builder()
         .timeout(Duration::from_secs(10))
         .build()
         .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
@@ -327,15 +1047,80 @@ pub async fn test_cloud_connection(
             }
         })?;
 
+    // 前端传入的 provider 与 CloudProvider 的 serde 名称一致，解析失败按自定义处理
+    let cloud_provider: CloudProvider = serde_json::from_value(serde_json::Value::String(provider.clone()))
+        .unwrap_or(CloudProvider::Custom);
+    let model_name = effective_model_name(&cloud_provider, model.as_deref());
+
     match resp.status().as_u16() {
-        200..=299 => Ok("连接成功".to_string()),
+        200..=299 => {
+            // 尝试在 /models 列表中查找实际使用的模型（部分服务不返回完整列表，找不到只提示不报错）
+            let listed = resp.json::<serde_json::Value>().await
+                .ok()
+                .and_then(|json| json["data"].as_array().cloned())
+                .map(|models| models.iter().any(|m| m["id"].as_str() == Some(model_name.as_str())));
+            match listed {
+                Some(false) => Ok(format!("连接成功，但模型列表中未找到 \"{}\"，请确认模型名", model_name)),
+                _ => Ok(format!("连接成功（模型：{}）", model_name)),
+            }
+        }
         401 | 403 => Err("API Key 无效或权限不足".to_string()),
-        404       => Ok("服务可达（/models 不支持，转写接口通常仍可用）".to_string()),
+        404       => Ok(format!("服务可达（/models 不支持，转写接口通常仍可用，模型：{}）", model_name)),
         429       => Err("请求频率超限，稍后再试".to_string()),
         code      => Err(format!("服务返回异常状态: {}", code)),
     }
 }
 
+/// 测试音频时长（秒）
+const CLOUD_TEST_CLIP_SECS: f64 = 1.0;
+
+/// 完整测试云端识别：用 1 秒合成音频走真实的转写接口
+///
+/// test_cloud_connection 只检查 /models，模型名错误、转写接口路径不对、
+/// Key 没有转写权限等问题要真正调用一次才能发现。
+/// 其余参数（语言、response_format、上传上限）取当前设置
+#[tauri::command]
+pub async fn test_cloud_transcription(
+    base_url: String,
+    api_key: String,
+    provider: String,      // 前端传入，如 "aliyun" / "openAI" / ...
+    model: Option<String>, // 模型名覆盖（留空 = 服务商默认）
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut settings = {
+        let inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.settings.clone()
+    };
+    settings.cloud_provider = serde_json::from_value(serde_json::Value::String(provider))
+        .unwrap_or(CloudProvider::Custom);
+    settings.cloud_base_url = base_url;
+    settings.cloud_api_key = api_key;
+    settings.cloud_model = model;
+
+    let clip = crate::commands::model::synthetic_reference_clip(CLOUD_TEST_CLIP_SECS);
+    let started = std::time::Instant::now();
+    let text = transcribe_with_cloud(&settings, clip).await?;
+    let elapsed_ms = started.elapsed().as_millis();
+
+    let model_name = effective_model_name(&settings.cloud_provider, settings.cloud_model.as_deref());
+    log::info!("云端识别测试成功（{}，{} ms）: \"{}\"", model_name, elapsed_ms, text);
+    if text.trim().is_empty() {
+        Ok(format!("识别接口正常（模型：{}，耗时 {} ms，测试音频无语音内容）", model_name, elapsed_ms))
+    } else {
+        Ok(format!("识别接口正常（模型：{}，耗时 {} ms），返回: {}", model_name, elapsed_ms, text))
+    }
+}
+
+/// 查询云端服务最近一次返回的限流 / 剩余额度信息
+///
+/// 信息来自识别请求的响应头（OpenAI 等会返回 x-ratelimit-*），
+/// 还没有发过请求或服务商不返回这些头时为 None
+#[tauri::command]
+pub async fn get_cloud_quota() -> Result<Option<crate::cloud::RateLimitInfo>, String> {
+    Ok(crate::cloud::last_rate_limit())
+}
+
 // ── 工具函数 ────────────────────────────────────────────────────────────────
 
 /// 生成简单唯一 ID（时间戳 + 纳秒，足够在单机上不重复）
@@ -346,3 +1131,66 @@ fn make_id() -> String {
         .unwrap_or_default();
     format!("{}{:09}", t.as_secs(), t.subsec_nanos())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn timestamp_prefix_uses_default_format_in_local_time() {
+        let ts = chrono::Utc.with_ymd_and_hms(2024, 5, 1, 8, 7, 0).unwrap();
+        let local = ts.with_timezone(&chrono::Local);
+        let expected = format!("{}你好", local.format("[%H:%M] "));
+        assert_eq!(with_timestamp_prefix("你好", &ts, "[%H:%M] "), expected);
+        // 形如 "[08:07] 你好"：方括号、两位时分、空格
+        assert!(expected.starts_with('[') && expected[..8].ends_with("] "));
+    }
+
+    #[test]
+    fn timestamp_prefix_skips_empty_text() {
+        let ts = chrono::Utc.with_ymd_and_hms(2024, 5, 1, 8, 7, 0).unwrap();
+        assert_eq!(with_timestamp_prefix("", &ts, "[%H:%M] "), "");
+    }
+
+    #[test]
+    fn inference_timeout_disabled_when_cap_is_zero() {
+        use crate::whisper::WhisperModel;
+        assert_eq!(inference_timeout_secs(&WhisperModel::Small, 30.0, 0), None);
+        assert_eq!(inference_timeout_secs(&WhisperModel::LargeV3, 3600.0, 0), None);
+    }
+
+    #[test]
+    fn inference_timeout_short_clip_uses_minimum() {
+        use crate::whisper::WhisperModel;
+        // 1 秒 × 0.5 + 20 秒余量 = 21 秒，低于下限
+        assert_eq!(
+            inference_timeout_secs(&WhisperModel::Tiny, 1.0, DEFAULT_INFERENCE_TIMEOUT_CAP_SECS),
+            Some(MIN_INFERENCE_TIMEOUT_SECS)
+        );
+        // cap 低于下限时仍按下限
+        assert_eq!(inference_timeout_secs(&WhisperModel::Tiny, 1.0, 10), Some(MIN_INFERENCE_TIMEOUT_SECS));
+    }
+
+    #[test]
+    fn inference_timeout_long_clip_is_capped() {
+        use crate::whisper::WhisperModel;
+        // 600 秒 × 10 + 20 秒余量远超上限
+        assert_eq!(inference_timeout_secs(&WhisperModel::LargeV3, 600.0, 600), Some(600));
+        // 介于两者之间时使用估算值：30 秒 × 2.0 + 20 秒余量
+        assert_eq!(
+            inference_timeout_secs(&WhisperModel::Small, 30.0, 600),
+            Some(60 + INFERENCE_TIMEOUT_MARGIN_SECS)
+        );
+    }
+
+    #[test]
+    fn invalid_timestamp_format_is_rejected() {
+        assert!(parse_timestamp_format("[%H:%M] ").is_some());
+        assert!(parse_timestamp_format("%Q").is_none());
+        assert!(parse_timestamp_format("[%H:%").is_none());
+
+        let ts = chrono::Utc.with_ymd_and_hms(2024, 5, 1, 8, 7, 0).unwrap();
+        assert_eq!(with_timestamp_prefix("你好", &ts, "%Q"), "你好");
+    }
+}
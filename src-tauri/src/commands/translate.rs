@@ -4,11 +4,14 @@
 //   简体 ↔ 繁体  →  zhconv（本地，纯 Rust，零 API 调用）
 //   其他方向     →  MyMemory 免费翻译 API（无需注册，1000次/天；填入 Key 后 10000次/天）
 
-use tauri::State;
+use tauri::{Emitter, State};
 use serde::Serialize;
 use zhconv::{zhconv, Variant};
 use chrono::Local;
-use crate::state::AppState;
+use crate::state::{AppState, InnerState};
+
+/// MyMemory 返回的匹配度低于此值时提示译文可能不准确（0~1，机器翻译通常约 0.85）
+const LOW_MATCH_THRESHOLD: f64 = 0.5;
 
 // ===== 语言代码映射 =====
 
@@ -22,6 +25,41 @@ fn to_mm_lang(lang: &str) -> &str {
     }
 }
 
+/// 推断文本的源语言（zh-hans / zh-hant / en）
+///
+/// hint：识别时记录的 whisper 语言码（如 "zh" / "en"），有则优先参考
+pub(crate) fn detect_source_lang(text: &str, hint: Option<&str>) -> &'static str {
+    let has_cjk = text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c));
+
+    match hint {
+        Some("en") if !has_cjk => return "en",
+        Some("zh") | Some("yue") => {}
+        _ if !has_cjk => return "en",
+        _ => {}
+    }
+
+    // 转为简体后有变化，说明原文含繁体字
+    if zhconv(text, Variant::ZhHans) != text {
+        "zh-hant"
+    } else {
+        "zh-hans"
+    }
+}
+
+/// 该方向是否只用 zhconv 本地转换（不消耗 API 额度）
+pub(crate) fn is_local_conversion(from: &str, to: &str) -> bool {
+    from == to
+        || (from == "zh-hans" && to == "zh-hant")
+        || (from == "zh-hant" && to == "zh-hans")
+}
+
+/// 今日 MyMemory 额度是否已用完（按本地计数估算）
+pub(crate) fn daily_quota_exhausted(inner: &InnerState) -> bool {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let limit = if inner.settings.my_memory_key.is_empty() { 1_000 } else { 10_000 };
+    inner.translation_day_date == today && inner.translation_day_count >= limit
+}
+
 // ===== 翻译用量响应 =====
 
 #[derive(Debug, Serialize)]
@@ -41,13 +79,25 @@ pub struct TranslationUsage {
 ///
 /// - from / to: "zh-hans" | "zh-hant" | "en"
 /// - 简↔繁 使用 zhconv 本地完成，不消耗 API 额度
-/// - 其他方向调用 MyMemory API
+/// - 其他方向调用 MyMemory API；匹配度偏低时发送 translation-warning 事件（仍返回译文）
 #[tauri::command]
 pub async fn translate_text(
     text: String,
     from: String,
     to: String,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
+) -> Result<String, String> {
+    translate(&state, &app, text, &from, &to).await
+}
+
+/// 翻译实现，供 translate_text 命令和历史记录翻译共用
+pub(crate) async fn translate(
+    state: &AppState,
+    app: &tauri::AppHandle,
+    text: String,
+    from: &str,
+    to: &str,
 ) -> Result<String, String> {
     // 相同语言：直接返回
     if from == to || text.trim().is_empty() {
@@ -70,9 +120,11 @@ pub async fn translate_text(
         inner.settings.my_memory_key.clone()
     };
 
-    let langpair = format!("{}|{}", to_mm_lang(&from), to_mm_lang(&to));
+    let langpair = format!("{}|{}", to_mm_lang(from), to_mm_lang(to));
 
-    let client = reqwest::Client::new();
+    let client = crate::net::client_builder()
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
     let mut req = client
         .get("https://api.mymemory.translated.net/get")
         .query(&[("q", text.as_str()), ("langpair", langpair.as_str())]);
@@ -87,18 +139,15 @@ pub async fn translate_text(
     let json: serde_json::Value = resp.json().await
         .map_err(|e| format!("解析响应失败: {}", e))?;
 
-    // 检查限额
-    let quota_finished = json["quotaFinished"].as_bool().unwrap_or(false);
-    let translated = json["responseData"]["translatedText"]
-        .as_str()
-        .unwrap_or("");
-
-    if quota_finished || translated.starts_with("MYMEMORY WARNING:") {
-        return Err("今日翻译次数已用完，请明天再试，或在设置中填入 MyMemory Key 提升至 10000次/天".to_string());
-    }
+    let translated = parse_mymemory_response(&json)?;
 
-    if translated.is_empty() {
-        return Err(format!("翻译失败，服务器响应: {}", json));
+    // 匹配度偏低：照常返回译文，同时提示前端标记为不确定
+    if let Some(score) = json["responseData"]["match"].as_f64() {
+        if score < LOW_MATCH_THRESHOLD {
+            let warning = format!("译文匹配度较低（{:.0}%），结果可能不准确", score * 100.0);
+            log::warn!("{}", warning);
+            let _ = app.emit("translation-warning", &warning);
+        }
     }
 
     // 更新今日计数
@@ -113,6 +162,39 @@ pub async fn translate_text(
         inner.translation_day_count += 1;
     }
 
+    Ok(translated)
+}
+
+/// 解析 MyMemory 响应：responseStatus 为 200 时返回译文，否则按 responseDetails 给出具体原因
+fn parse_mymemory_response(json: &serde_json::Value) -> Result<String, String> {
+    // responseStatus 有时是数字，有时是字符串（如 "403"）
+    let status = json["responseStatus"].as_u64()
+        .or_else(|| json["responseStatus"].as_str().and_then(|s| s.trim().parse().ok()))
+        .unwrap_or(0);
+    let details = json["responseDetails"].as_str().unwrap_or("");
+    let translated = json["responseData"]["translatedText"].as_str().unwrap_or("");
+
+    let quota_finished = json["quotaFinished"].as_bool().unwrap_or(false);
+    if quota_finished || status == 429 || translated.starts_with("MYMEMORY WARNING:") {
+        return Err("今日翻译次数已用完，请明天再试，或在设置中填入 MyMemory Key 提升至 10000次/天".to_string());
+    }
+
+    if status != 200 {
+        let upper = details.to_uppercase();
+        return Err(if upper.contains("NO QUERY SPECIFIED") {
+            "翻译失败：待翻译文本为空".to_string()
+        } else if upper.contains("INVALID") && (upper.contains("LANGUAGE") || upper.contains("LANGPAIR")) {
+            format!("翻译失败：不支持的语言方向（{}）", details)
+        } else if details.is_empty() {
+            format!("翻译失败（状态码 {}）", status)
+        } else {
+            format!("翻译失败（状态码 {}）: {}", status, details)
+        });
+    }
+
+    if translated.is_empty() {
+        return Err("翻译失败：服务器未返回译文".to_string());
+    }
     Ok(translated.to_string())
 }
 
@@ -142,7 +224,8 @@ pub async fn get_translation_usage(
 
     // 有 Key 时尝试获取精确用量
     if has_key {
-        let client = reqwest::Client::new();
+        let client = crate::net::client_builder().build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
         if let Ok(resp) = client
             .get("https://api.mymemory.translated.net/usage")
             .query(&[("key", api_key.as_str())])
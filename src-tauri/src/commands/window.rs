@@ -1,6 +1,29 @@
 // commands/window.rs - 窗口控制命令
 
-use tauri::{Manager, Emitter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, Emitter, PhysicalPosition, PhysicalSize, State, WindowEvent};
+use tauri_plugin_store::StoreExt;
+use crate::state::AppState;
+
+/// 窗口位置和大小在设置存储中的键（与设置同一个文件，不属于 AppSettings）
+const WINDOW_GEOMETRY_KEY: &str = "window_geometry";
+
+/// 窗口位置写盘防抖间隔（毫秒）：拖动 / 缩放时会连续触发大量事件
+const GEOMETRY_SAVE_DEBOUNCE_MS: u64 = 500;
+
+/// 最近一次移动 / 缩放的序号，防抖任务到期时序号未变才写盘
+static GEOMETRY_SAVE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 悬浮窗位置和大小（物理像素；位置为外框左上角，大小为内容区）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
 
 /// 切换悬浮窗的显示/隐藏
 #[tauri::command]
@@ -16,9 +39,15 @@ pub async fn toggle_window_visibility(
             Ok(false)
         } else {
             window.show().map_err(|e| format!("显示窗口失败: {}", e))?;
-            // macOS：show() 后重新断言置顶，防止窗口层级被重置
+            // macOS：show() 后重新断言置顶，防止窗口层级被重置（关闭置顶时不处理）
             #[cfg(target_os = "macos")]
-            let _ = window.set_always_on_top(true);
+            {
+                let state = app.state::<AppState>();
+                let on_top = state.inner.lock().map(|inner| inner.settings.always_on_top).unwrap_or(true);
+                if on_top {
+                    let _ = window.set_always_on_top(true);
+                }
+            }
             Ok(true)
         }
     } else {
@@ -26,6 +55,34 @@ pub async fn toggle_window_visibility(
     }
 }
 
+/// 开启 / 关闭悬浮窗置顶，立即生效并保存到设置
+#[tauri::command]
+pub async fn set_always_on_top(
+    enabled: bool,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    apply_always_on_top(&app, enabled)?;
+
+    {
+        let mut inner = state.inner.lock()
+            .map_err(|e| format!("状态锁失败: {}", e))?;
+        inner.settings.always_on_top = enabled;
+    }
+    crate::commands::settings::schedule_persist_settings(&app, &state)?;
+
+    log::info!("悬浮窗置顶: {}", enabled);
+    Ok(())
+}
+
+/// 把置顶设置应用到主窗口（启动、保存设置时调用）
+pub(crate) fn apply_always_on_top(app: &tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let window = app.get_webview_window("main")
+        .ok_or_else(|| "未找到主窗口".to_string())?;
+    window.set_always_on_top(enabled)
+        .map_err(|e| format!("设置窗口置顶失败: {}", e))
+}
+
 /// 设置窗口透明度
 /// opacity: 0.0（完全透明）到 1.0（完全不透明）
 #[tauri::command]
@@ -46,3 +103,113 @@ pub async fn set_window_opacity(
         Err("未找到主窗口".to_string())
     }
 }
+
+/// 窗口事件回调（注册在 Builder 上）：主窗口移动 / 缩放后防抖保存位置和大小
+pub(crate) fn handle_window_event(window: &tauri::Window, event: &WindowEvent) {
+    if window.label() != "main" {
+        return;
+    }
+    if !matches!(event, WindowEvent::Moved(_) | WindowEvent::Resized(_)) {
+        return;
+    }
+
+    let generation = GEOMETRY_SAVE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = window.app_handle().clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(GEOMETRY_SAVE_DEBOUNCE_MS)).await;
+        if GEOMETRY_SAVE_GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        if let Err(e) = save_window_geometry(&app) {
+            log::warn!("保存窗口位置失败: {}", e);
+        }
+    });
+}
+
+/// 把主窗口当前位置和大小写入设置存储（最小化时跳过，此时坐标无意义）
+pub(crate) fn save_window_geometry(app: &tauri::AppHandle) -> Result<(), String> {
+    let window = app.get_webview_window("main")
+        .ok_or_else(|| "未找到主窗口".to_string())?;
+    if window.is_minimized().unwrap_or(false) {
+        return Ok(());
+    }
+
+    let position = window.outer_position()
+        .map_err(|e| format!("获取窗口位置失败: {}", e))?;
+    let size = window.inner_size()
+        .map_err(|e| format!("获取窗口大小失败: {}", e))?;
+    let geometry = WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    };
+
+    let store = app.store(crate::commands::settings::STORE_FILE)
+        .map_err(|e| format!("打开存储失败: {}", e))?;
+    let val = serde_json::to_value(geometry)
+        .map_err(|e| format!("序列化窗口位置失败: {}", e))?;
+    store.set(WINDOW_GEOMETRY_KEY, val);
+    store.save()
+        .map_err(|e| format!("写入磁盘失败: {}", e))?;
+
+    log::debug!("已保存窗口位置: {:?}", geometry);
+    Ok(())
+}
+
+/// 启动时恢复上次保存的窗口位置和大小（没有保存过时保持默认位置）
+///
+/// 保存时所在的显示器可能已断开：窗口中心不在任何显示器内时移到主显示器，
+/// 并限制在显示器范围内，保证窗口总是可见
+pub(crate) fn restore_window_geometry(app: &tauri::AppHandle) -> Result<(), String> {
+    let store = app.store(crate::commands::settings::STORE_FILE)
+        .map_err(|e| format!("打开存储失败: {}", e))?;
+    let Some(val) = store.get(WINDOW_GEOMETRY_KEY) else {
+        return Ok(());
+    };
+    let saved: WindowGeometry = serde_json::from_value(val)
+        .map_err(|e| format!("窗口位置格式无效: {}", e))?;
+
+    let window = app.get_webview_window("main")
+        .ok_or_else(|| "未找到主窗口".to_string())?;
+
+    let center_x = saved.x as i64 + saved.width as i64 / 2;
+    let center_y = saved.y as i64 + saved.height as i64 / 2;
+    let contains_center = |m: &tauri::Monitor| {
+        let (pos, size) = (m.position(), m.size());
+        (pos.x as i64..pos.x as i64 + size.width as i64).contains(&center_x)
+            && (pos.y as i64..pos.y as i64 + size.height as i64).contains(&center_y)
+    };
+    let monitor = window.available_monitors()
+        .ok()
+        .and_then(|monitors| monitors.into_iter().find(contains_center))
+        .or_else(|| window.primary_monitor().ok().flatten())
+        .ok_or_else(|| "未找到可用的显示器".to_string())?;
+
+    let geometry = clamp_to_monitor(saved, monitor.position(), monitor.size());
+    window.set_size(PhysicalSize::new(geometry.width, geometry.height))
+        .map_err(|e| format!("设置窗口大小失败: {}", e))?;
+    window.set_position(PhysicalPosition::new(geometry.x, geometry.y))
+        .map_err(|e| format!("设置窗口位置失败: {}", e))?;
+
+    log::info!("已恢复窗口位置: {:?}", geometry);
+    Ok(())
+}
+
+/// 把窗口限制在显示器范围内（窗口比显示器大时缩小到显示器大小）
+fn clamp_to_monitor(
+    geometry: WindowGeometry,
+    origin: &PhysicalPosition<i32>,
+    size: &PhysicalSize<u32>,
+) -> WindowGeometry {
+    let width = geometry.width.min(size.width);
+    let height = geometry.height.min(size.height);
+    let max_x = origin.x + (size.width - width) as i32;
+    let max_y = origin.y + (size.height - height) as i32;
+    WindowGeometry {
+        x: geometry.x.clamp(origin.x, max_x),
+        y: geometry.y.clamp(origin.y, max_y),
+        width,
+        height,
+    }
+}
@@ -8,43 +8,179 @@ pub mod cloud;      // 云端 API 调用模块
 pub mod commands;   // Tauri 命令（前端通过 invoke 调用）
 pub mod state;      // 全局应用状态
 pub mod tray;       // 系统托盘
+pub mod net;        // 出站 HTTP 客户端（统一代理 / User-Agent）
 
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// 从 panic payload 中取出可读信息（&str / String，其他类型给通用描述）
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "未知 panic".to_string()
+    }
+}
+
+/// 按字符截取文本前 max_chars 个字符，用于日志和通知预览
+/// 按字节切片会在多字节字符（中文 = 3 字节/字符）中间切割导致 panic
+pub(crate) fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// 安装全局 panic hook：记录 panic 信息和调用栈，并弹出系统通知
+/// 推理线程栈溢出等崩溃以前没有任何用户可见的痕迹，这里至少留下日志
+fn install_panic_hook(app: tauri::AppHandle) {
+    let log_dir = app.path().app_log_dir()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let location = info.location()
+            .map(|l| format!("{}:{}", l.file(), l.line()))
+            .unwrap_or_else(|| "未知位置".to_string());
+        log::error!(
+            "线程 {} 发生 panic（{}）: {}\n{}",
+            thread.name().unwrap_or("<unnamed>"),
+            location,
+            panic_message(info.payload()),
+            std::backtrace::Backtrace::force_capture()
+        );
+
+        let _ = app.notification()
+            .builder()
+            .title("Voxie")
+            .body(format!("Voxie 遇到错误，详情见日志（{}）", log_dir))
+            .show();
+
+        default_hook(info);
+    }));
+}
+
+/// 退出时等待下载任务清理不完整文件的最长时间（毫秒）
+const SHUTDOWN_DOWNLOAD_WAIT_MS: u64 = 3000;
+
+/// 退出前的清理：停止录音流（含预录常驻流），通知进行中的下载中止
+///
+/// 返回 true 表示还有下载在清理，需要阻止本次退出，清理完成后会再次调用 exit
+fn prepare_shutdown(app: &tauri::AppHandle) -> bool {
+    let app_state = app.state::<state::AppState>();
+
+    let active_downloads = {
+        let Ok(mut inner) = app_state.inner.lock() else { return false };
+        if inner.shutting_down {
+            // 第二次进入：清理已完成（或等待超时），放行
+            return false;
+        }
+        inner.shutting_down = true;
+        inner.downloads.values()
+            .filter(|d| d.status == state::ModelStatus::Downloading)
+            .count()
+    };
+
+    if let Ok(mut recorder) = app_state.recorder.lock() {
+        if recorder.is_recording() {
+            let _ = recorder.stop();
+            log::info!("退出前已停止录音");
+        }
+        let _ = recorder.set_pre_roll(0);
+    }
+
+    if active_downloads == 0 {
+        return false;
+    }
+
+    // 下载任务在下一个数据块时发现 shutting_down，删除不完整文件后结束
+    log::info!("等待 {} 个下载任务中止...", active_downloads);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let started = std::time::Instant::now();
+        while started.elapsed() < std::time::Duration::from_millis(SHUTDOWN_DOWNLOAD_WAIT_MS) {
+            let downloading = app.state::<state::AppState>().inner.lock()
+                .map(|inner| inner.downloads.values().any(|d| d.status == state::ModelStatus::Downloading))
+                .unwrap_or(false);
+            if !downloading {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        app.exit(0);
+    });
+    true
+}
+
+/// 日志文件名（不含扩展名），写在应用日志目录下（app_log_dir），
+/// 打包后的 GUI 程序没有控制台，panic 通知中"详情见日志"指的就是这个文件
+pub(crate) const LOG_FILE_NAME: &str = "voxie";
+
+/// 单个日志文件的上限（字节），超过后轮转，只保留一个旧文件
+const LOG_MAX_FILE_BYTES: u128 = 5 * 1024 * 1024;
+
+/// 日志插件：同时输出到控制台和日志文件
+/// 调试模式：voxie 模块 debug 日志；发布模式：voxie 模块 info 日志（用于诊断 Whisper 等运行时问题）
+fn log_plugin<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
 
-/// 应用程序主入口函数
-/// 由 main.rs 调用
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    // 初始化日志系统
-    // 调试模式：显示所有日志
-    // 发布模式：显示 voxie 模块的 info 日志（用于诊断 Whisper 等运行时问题）
     #[cfg(debug_assertions)]
-    std::env::set_var("RUST_LOG", "voxie=debug,tauri=info");
+    let (app_level, tauri_level) = (log::LevelFilter::Debug, log::LevelFilter::Info);
     #[cfg(not(debug_assertions))]
-    std::env::set_var("RUST_LOG", "voxie=info,tauri=warn");
-
-    env_logger::init();
+    let (app_level, tauri_level) = (log::LevelFilter::Info, log::LevelFilter::Warn);
 
-    log::info!("Voxie 启动中...");
+    tauri_plugin_log::Builder::new()
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::LogDir { file_name: Some(LOG_FILE_NAME.to_string()) }),
+        ])
+        .level(log::LevelFilter::Warn)
+        .level_for("voxie_lib", app_level)
+        .level_for("voxie", app_level)
+        .level_for("tauri", tauri_level)
+        .max_file_size(LOG_MAX_FILE_BYTES)
+        .rotation_strategy(RotationStrategy::KeepOne)
+        .build()
+}
 
+/// 应用程序主入口函数
+/// 由 main.rs 调用
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
     // 构建 Tauri 应用
     tauri::Builder::default()
         // ===== 注册插件 =====
+        // 日志插件最先注册，其他插件初始化时的日志也能写入文件
+        .plugin(log_plugin())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_store::Builder::new().build())
-        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(commands::shortcut::handle_shortcut)
+                .build(),
+        )
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            None,
+        ))
         // ===== 注册全局应用状态 =====
         // 这里注册的状态可以在所有 Tauri 命令中通过参数注入获取
         .manage(state::AppState::new())
         // ===== 应用启动时的初始化逻辑 =====
         .setup(|app| {
+            log::info!("Voxie 启动中...");
             log::info!("应用 setup 开始");
 
+            install_panic_hook(app.handle().clone());
+
             // ── 从磁盘加载持久化设置 ──
             {
                 let app_state = app.state::<state::AppState>();
@@ -52,29 +188,56 @@ pub fn run() {
                     app.handle(),
                     &app_state,
                 );
+                commands::history::load_persisted_history(
+                    app.handle(),
+                    &app_state,
+                );
+            }
+
+            // 本地模型空闲自动卸载
+            commands::model::spawn_idle_unloader(app.handle().clone());
+
+            // 初始化系统托盘（失败只记录，不阻止启动）
+            if let Err(e) = tray::setup_tray(app) {
+                log::warn!("系统托盘初始化失败: {}", e);
             }
 
-            // 初始化系统托盘
-            tray::setup_tray(app)?;
-
-            // 注册全局快捷键（默认右 Option 键）
-            // 注意：全局快捷键在这里只是初始化框架，
-            // 实际的监听逻辑由前端配置后通过 command 注册
-            log::info!("全局快捷键框架初始化完成");
-
-            // 获取主窗口并配置
-            if let Some(window) = app.get_webview_window("main") {
-                // macOS 特有：设置窗口始终置顶
-                #[cfg(target_os = "macos")]
-                {
-                    window.set_always_on_top(true)?;
-                    log::info!("悬浮窗置顶设置完成");
+            // 注册录音快捷键（按下 / 松开的处理见 commands::shortcut）
+            // 注册失败（格式无效 / 被占用）不阻止启动，通过 config-warning 提示
+            {
+                let app_state = app.state::<state::AppState>();
+                let shortcut_key = app_state.inner.lock()
+                    .map(|inner| inner.settings.shortcut_key.clone())
+                    .unwrap_or_default();
+                if let Err(e) = commands::shortcut::apply_shortcut(app.handle(), &shortcut_key) {
+                    log::warn!("{}", e);
+                    let _ = app.emit("config-warning", &e);
+                }
+            }
+
+            // 按设置配置主窗口置顶（tauri.conf.json 默认置顶，用户可关闭）
+            {
+                let app_state = app.state::<state::AppState>();
+                let on_top = app_state.inner.lock()
+                    .map(|inner| inner.settings.always_on_top)
+                    .unwrap_or(true);
+                if let Err(e) = commands::window::apply_always_on_top(app.handle(), on_top) {
+                    log::warn!("{}", e);
+                } else {
+                    log::info!("悬浮窗置顶设置完成: {}", on_top);
                 }
             }
 
+            // 恢复上次的窗口位置和大小（失败只记录，保持默认位置）
+            if let Err(e) = commands::window::restore_window_geometry(app.handle()) {
+                log::warn!("恢复窗口位置失败: {}", e);
+            }
+
             log::info!("应用初始化完成");
             Ok(())
         })
+        // 主窗口移动 / 缩放后保存位置和大小
+        .on_window_event(commands::window::handle_window_event)
         // ===== 注册 Tauri 命令 =====
         // 前端通过 invoke('command_name', args) 调用这些函数
         .invoke_handler(tauri::generate_handler![
@@ -82,33 +245,117 @@ pub fn run() {
             commands::audio::start_recording,
             commands::audio::stop_recording,
             commands::audio::get_recording_status,
+            commands::audio::reset_state,
+            commands::audio::test_microphone,
+            commands::audio::set_continuous_dictation,
+            commands::audio::get_device_capabilities,
+            commands::audio::list_audio_hosts,
+            commands::audio::list_input_devices,
+            commands::audio::save_recording_wav,
+            commands::audio::get_last_recording_pcm,
             // 识别相关命令
             commands::transcribe::transcribe_audio,
+            commands::transcribe::cancel_transcription,
+            commands::transcribe::transcribe_audio_detailed,
             commands::transcribe::get_transcription_status,
             commands::transcribe::test_cloud_connection,
+            commands::transcribe::test_cloud_transcription,
+            commands::transcribe::check_config,
+            commands::transcribe::provider_supports_language,
+            commands::transcribe::get_cloud_quota,
             // 翻译命令
             commands::translate::translate_text,
             commands::translate::get_translation_usage,
             // 模型管理命令
             commands::model::download_model,
             commands::model::load_whisper_model,
+            commands::model::check_model_host,
+            commands::model::get_active_model,
+            commands::model::benchmark_model,
             commands::model::unload_whisper_model,
             commands::model::get_model_status,
             commands::model::list_models,
+            commands::model::import_model,
             commands::model::delete_model,
+            commands::model::refresh_models,
+            commands::model::set_models_dir,
             // 设置命令
             commands::settings::get_settings,
             commands::settings::save_settings,
+            commands::settings::flush_settings,
+            commands::settings::set_language,
+            commands::settings::set_transcription_mode,
+            commands::settings::list_supported_languages,
             // 历史记录命令
             commands::history::get_history,
+            commands::history::search_history,
             commands::history::clear_history,
+            commands::history::clear_history_before,
             commands::history::delete_history_item,
+            commands::history::update_history_item,
+            commands::history::toggle_history_pin,
+            commands::history::translate_history_item,
+            // 导出命令
+            commands::export::export_sample,
+            commands::export::export_history_item,
+            // 诊断命令
+            commands::diagnostics::get_diagnostics,
+            // 存储管理命令
+            commands::storage::get_storage_usage,
+            commands::storage::clean_storage,
+            // 开机自启动命令
+            commands::autostart::enable_autostart,
+            commands::autostart::disable_autostart,
+            commands::autostart::is_autostart_enabled,
             // 剪贴板命令
             commands::clipboard::copy_to_clipboard,
+            commands::clipboard::copy_last_result,
             // 窗口命令
             commands::window::toggle_window_visibility,
             commands::window::set_window_opacity,
+            commands::window::set_always_on_top,
         ])
-        .run(tauri::generate_context!())
-        .expect("Tauri 应用启动失败");
+        .build(tauri::generate_context!())
+        .expect("Tauri 应用启动失败")
+        .run(|app, event| match event {
+            // 退出请求：先停止录音、中止下载，下载清理完成前阻止退出
+            tauri::RunEvent::ExitRequested { api, .. } => {
+                if prepare_shutdown(app) {
+                    api.prevent_exit();
+                }
+            }
+            // 退出前写入防抖中尚未落盘的设置
+            tauri::RunEvent::Exit => {
+                let app_state = app.state::<state::AppState>();
+                if let Err(e) = commands::settings::flush_pending_settings(app, &app_state, false) {
+                    log::error!("退出前保存设置失败: {}", e);
+                }
+            }
+            _ => {}
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::truncate_chars;
+
+    #[test]
+    fn truncate_chars_keeps_whole_multibyte_characters() {
+        // 每个中文字符 3 字节，第 50 个字节落在第 17 个字符中间
+        let text = "语音识别测试".repeat(10);
+        assert!(!text.is_char_boundary(50));
+
+        let truncated = truncate_chars(&text, 50);
+        assert_eq!(truncated.chars().count(), 50);
+        assert!(text.starts_with(truncated));
+
+        let short = truncate_chars(&text, 17);
+        assert_eq!(short, text.chars().take(17).collect::<String>());
+    }
+
+    #[test]
+    fn truncate_chars_returns_short_text_unchanged() {
+        assert_eq!(truncate_chars("你好", 50), "你好");
+        assert_eq!(truncate_chars("", 5), "");
+    }
 }
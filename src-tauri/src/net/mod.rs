@@ -0,0 +1,55 @@
+// net/mod.rs - 出站 HTTP 客户端
+//
+// 云端识别、翻译、模型下载、统计上报等所有出站请求都通过 client_builder() 创建客户端，
+// 统一附加 User-Agent、Accept 和用户配置的代理，网络行为只需在这里查看和调整
+
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
+use std::sync::RwLock;
+
+/// 出站请求的 User-Agent
+/// 部分服务商和 Hugging Face 会限流或拒绝默认 UA，带上应用名和版本也便于服务端识别
+const USER_AGENT: &str = concat!("Voxie/", env!("CARGO_PKG_VERSION"));
+
+/// 默认 Accept：接口返回 JSON 为主，模型文件等二进制下载同样接受
+const DEFAULT_ACCEPT: &str = "application/json, */*;q=0.8";
+
+/// 用户配置的 HTTP 代理（来自 settings.http_proxy，None = 使用系统代理环境变量）
+/// 与模型镜像相同，创建客户端时无法访问设置，覆盖值放在全局
+static PROXY_OVERRIDE: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+
+/// 设置（或清除）HTTP 代理，空字符串视为清除
+pub fn set_proxy_override(proxy: Option<String>) {
+    let proxy = proxy
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    if let Ok(mut guard) = PROXY_OVERRIDE.write() {
+        *guard = proxy;
+    }
+}
+
+/// 校验代理地址（如 http://127.0.0.1:7890）
+pub fn validate_proxy(proxy: &str) -> Result<(), String> {
+    reqwest::Proxy::all(proxy)
+        .map(|_| ())
+        .map_err(|e| format!("代理地址无效: {}（{}）", proxy, e))
+}
+
+/// 创建带统一配置的客户端构建器，调用方再按需设置超时
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static(DEFAULT_ACCEPT));
+    let mut builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .default_headers(headers);
+
+    let proxy = PROXY_OVERRIDE.read().ok().and_then(|guard| guard.clone());
+    if let Some(proxy) = proxy {
+        match reqwest::Proxy::all(&proxy) {
+            Ok(p) => builder = builder.proxy(p),
+            // 保存设置时已校验，这里失败只记录并直连
+            Err(e) => log::warn!("代理地址无效，已忽略: {}（{}）", proxy, e),
+        }
+    }
+    builder
+}
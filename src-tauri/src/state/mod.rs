@@ -3,7 +3,11 @@
 // Arc = 原子引用计数（允许多线程共享所有权）
 // Mutex = 互斥锁（同一时间只允许一个线程访问）
 
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use crate::audio::AudioRecorder;
@@ -42,6 +46,18 @@ impl Default for ModelStatus {
     fn default() -> Self { ModelStatus::NotDownloaded }
 }
 
+// ===== 下载状态 =====
+
+/// 单个模型的下载状态（允许多个模型同时下载，各自独立跟踪）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadState {
+    /// Downloading / Downloaded / Error
+    pub status: ModelStatus,
+    /// 0.0 - 1.0
+    pub progress: f64,
+}
+
 // ===== 识别模式 =====
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -55,6 +71,21 @@ impl Default for TranscriptionMode {
     fn default() -> Self { TranscriptionMode::Local }
 }
 
+// ===== 快捷键录音方式 =====
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordingTriggerMode {
+    /// 按一次开始、再按一次停止
+    Toggle,
+    /// 按住说话：按下开始、松开停止
+    PushToTalk,
+}
+
+impl Default for RecordingTriggerMode {
+    fn default() -> Self { RecordingTriggerMode::Toggle }
+}
+
 // ===== 历史记录 =====
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,7 +96,26 @@ pub struct HistoryItem {
     pub timestamp: DateTime<Utc>,
     pub duration_ms: u64,
     pub mode: TranscriptionMode,
+    /// 本地：模型文件名（如 ggml-small.bin）；云端：服务商/模型（如 OpenAI/whisper-1）
     pub model_name: Option<String>,
+    /// 识别语言（指定的语言或 whisper 自动检测到的语言）
+    #[serde(default)]
+    pub language: Option<String>,
+    /// 本地识别失败后由云端回退完成
+    #[serde(default)]
+    pub is_fallback: bool,
+    /// 最近一次对该条记录的翻译结果
+    #[serde(default)]
+    pub translation: Option<String>,
+    /// 带时间戳的段落（运行过带时间戳识别时才有，用于导出字幕）
+    #[serde(default)]
+    pub segments: Option<Vec<crate::whisper::Segment>>,
+    /// 文本是 whisper 翻译成的英文，而不是原语言转录（language 仍为原语言）
+    #[serde(default)]
+    pub is_translation: bool,
+    /// 已固定：超出 max_history 时不会被删除
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 // ===== 云端服务商 =====
@@ -77,6 +127,9 @@ pub enum CloudProvider {
     VolcEngine,  // 火山引擎
     Aliyun,      // 阿里云
     Xunfei,      // 讯飞
+    Groq,        // Groq（OpenAI 兼容，whisper-large-v3）
+    Deepgram,    // Deepgram（原生接口，nova-2）
+    Azure,       // Azure 语音服务（短音频 REST 接口）
     Custom,
 }
 
@@ -87,6 +140,19 @@ impl Default for CloudProvider {
 // ===== 应用设置 =====
 
 fn default_theme() -> String { "green".to_string() }
+fn default_cloud_response_format() -> String { "json".to_string() }
+fn default_clipboard_restore_delay_ms() -> u64 { 1500 }
+fn default_auto_stop_threshold() -> f32 { 0.01 }
+fn default_level_attack() -> f32 { 0.6 }
+fn default_level_release() -> f32 { 0.15 }
+fn default_min_recording_ms() -> u64 { 300 }
+fn default_always_on_top() -> bool { true }
+fn default_auto_copy_suffix() -> String { "none".to_string() }
+fn default_timestamp_prefix_format() -> String { "[%H:%M] ".to_string() }
+fn default_inference_timeout_cap_secs() -> u64 { crate::commands::transcribe::DEFAULT_INFERENCE_TIMEOUT_CAP_SECS }
+fn default_cloud_max_upload_mb() -> u64 { 25 }
+fn default_cloud_max_retries() -> u32 { 2 }
+fn default_max_recording_secs() -> u64 { 300 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -97,6 +163,9 @@ pub struct AppSettings {
     pub cloud_base_url: String,
     pub cloud_api_key: String,
     pub shortcut_key: String,
+    /// 快捷键录音方式："toggle" / "pushToTalk"
+    #[serde(default)]
+    pub recording_trigger_mode: RecordingTriggerMode,
     pub language: String,
     pub window_opacity: f64,
     pub auto_copy: bool,
@@ -107,6 +176,123 @@ pub struct AppSettings {
     /// MyMemory 翻译 API Key（可选，留空免费 1000次/天，填入后 10000次/天）
     #[serde(default)]
     pub my_memory_key: String,
+    /// 云端 ASR 模型名覆盖（留空 = 使用服务商默认模型，如 whisper-1）
+    #[serde(default)]
+    pub cloud_model: Option<String>,
+    /// 云端 ASR 的 response_format：json | text
+    #[serde(default = "default_cloud_response_format")]
+    pub cloud_response_format: String,
+    /// 云端上传音频大小上限（MB，0 = 不限制），OpenAI 限制为 25MB
+    #[serde(default = "default_cloud_max_upload_mb")]
+    pub cloud_max_upload_mb: u64,
+    /// 云端识别遇到网络错误 / 429 / 5xx 时的最大重试次数（0 = 不重试），鉴权失败不重试
+    #[serde(default = "default_cloud_max_retries")]
+    pub cloud_max_retries: u32,
+    /// 复制识别结果后，延迟恢复剪贴板原有内容（照顾剪贴板管理器用户）
+    #[serde(default)]
+    pub restore_clipboard: bool,
+    /// 恢复剪贴板前的等待时间（毫秒），需足够完成粘贴
+    #[serde(default = "default_clipboard_restore_delay_ms")]
+    pub clipboard_restore_delay_ms: u64,
+    /// 本地识别失败/超时时，自动用云端重试（需已配置云端凭据）
+    #[serde(default)]
+    pub fallback_to_cloud: bool,
+    /// 静音自动停止：说话后静音持续多少毫秒自动停止录音（0 = 关闭）
+    #[serde(default)]
+    pub auto_stop_silence_ms: u64,
+    /// 静音判定阈值（RMS，低于此值视为静音）
+    #[serde(default = "default_auto_stop_threshold")]
+    pub auto_stop_threshold: f32,
+    /// 单次录音最长时长（秒，0 = 不限制）：超过后自动停止并识别已录部分
+    #[serde(default = "default_max_recording_secs")]
+    pub max_recording_secs: u64,
+    /// 首尾静音裁剪阈值（RMS，0 = 不裁剪）：识别前去掉录音开头 / 结尾的空白，减少推理时间
+    #[serde(default)]
+    pub trim_silence_threshold: f32,
+    /// 噪声门阈值（样本绝对值，0 = 关闭）：混音后低于阈值的样本置零，压低底噪和键盘声
+    #[serde(default)]
+    pub noise_gate_threshold: f32,
+    /// 音量表平滑系数（0~1，越大跟随越快）：音量上升时
+    #[serde(default = "default_level_attack")]
+    pub level_attack: f32,
+    /// 音量表平滑系数：音量回落时
+    #[serde(default = "default_level_release")]
+    pub level_release: f32,
+    /// 悬浮窗隐藏时，识别完成后弹出系统通知
+    #[serde(default)]
+    pub notify_on_complete: bool,
+    /// 开机自启动（默认关闭）
+    #[serde(default)]
+    pub autostart: bool,
+    /// 分声道录音：多声道设备的各声道分别识别并标注（如采访时左右声道各一人）
+    #[serde(default)]
+    pub preserve_channels: bool,
+    /// 音频后端名称（如 "WASAPI" / "ASIO"，None = 系统默认）
+    #[serde(default)]
+    pub audio_host: Option<String>,
+    /// 输入设备名称（None = 系统默认设备），找不到时回退到默认设备
+    #[serde(default)]
+    pub input_device: Option<String>,
+    /// 预录时长（毫秒，0 = 关闭）：保留按下快捷键前的一小段音频，避免吞掉第一个字
+    #[serde(default)]
+    pub pre_roll_ms: u64,
+    /// 最短录音时长（毫秒），短于此视为误触，不进行识别（0 = 不限制）
+    #[serde(default = "default_min_recording_ms")]
+    pub min_recording_ms: u64,
+    /// 录音过短时静默丢弃（false = 返回"录音过短"错误）
+    #[serde(default)]
+    pub discard_short_recordings: bool,
+    /// 本地识别每段最大字符数（0 = 不限制）
+    #[serde(default)]
+    pub max_segment_len: u32,
+    /// 强制单段输出（None = 5 秒以下自动启用）
+    #[serde(default)]
+    pub force_single_segment: Option<bool>,
+    /// 本地识别的初始提示词（专有名词、术语表等，帮助 whisper 写对生僻词；空 = 不使用）
+    #[serde(default)]
+    pub initial_prompt: String,
+    /// 本地识别直接输出英文译文（whisper 内置翻译，与 MyMemory 文本翻译无关；云端识别不受影响）
+    #[serde(default)]
+    pub translate_to_english: bool,
+    /// 自动复制时追加的后缀："none" | "space" | "newline"（只影响剪贴板，不影响历史文本）
+    #[serde(default = "default_auto_copy_suffix")]
+    pub auto_copy_suffix: String,
+    /// 复制时在文本前加上识别时间（本地时区，只影响剪贴板，不影响历史文本）
+    #[serde(default)]
+    pub insert_timestamp_prefix: bool,
+    /// 时间前缀格式（chrono strftime 语法，如 "[%H:%M] "）
+    #[serde(default = "default_timestamp_prefix_format")]
+    pub timestamp_prefix_format: String,
+    /// 识别完成后自动翻译的目标语言（"zh-hans" | "zh-hant" | "en"，None = 关闭）
+    #[serde(default)]
+    pub translate_on_complete: Option<String>,
+    /// 悬浮窗始终置顶（默认开启）
+    #[serde(default = "default_always_on_top")]
+    pub always_on_top: bool,
+    /// 识别语言为英语时优先使用已下载的同尺寸 .en 模型（更快更准）
+    #[serde(default)]
+    pub prefer_english_models: bool,
+    /// 本地模型空闲多少秒后自动卸载以释放内存（0 = 不自动卸载）
+    #[serde(default)]
+    pub unload_after_idle_secs: u64,
+    /// 本地推理线程数（None = 按 CPU 自动选择）
+    #[serde(default)]
+    pub whisper_threads: Option<i32>,
+    /// 本地推理超时上限（秒）：实际超时按音频时长和模型估算，不超过此值（0 = 不限制超时）
+    #[serde(default = "default_inference_timeout_cap_secs")]
+    pub inference_timeout_cap_secs: u64,
+    /// 模型下载镜像站（None = huggingface.co），如 https://hf-mirror.com
+    #[serde(default)]
+    pub model_mirror_base: Option<String>,
+    /// 出站请求使用的 HTTP 代理（None = 使用系统代理环境变量），如 http://127.0.0.1:7890
+    #[serde(default)]
+    pub http_proxy: Option<String>,
+    /// 匿名识别耗时统计的上报地址（None = 关闭）：只含耗时与模型等分类字段，不含识别文本
+    #[serde(default)]
+    pub metrics_endpoint: Option<String>,
+    /// 自定义模型存储目录（None = 默认的用户数据目录）
+    #[serde(default)]
+    pub models_dir: Option<PathBuf>,
 }
 
 impl Default for AppSettings {
@@ -117,13 +303,53 @@ impl Default for AppSettings {
             cloud_provider: CloudProvider::OpenAI,
             cloud_base_url: "https://api.openai.com/v1".to_string(),
             cloud_api_key: String::new(),
-            shortcut_key: "Alt".to_string(),
+            shortcut_key: crate::commands::shortcut::DEFAULT_SHORTCUT_KEY.to_string(),
+            recording_trigger_mode: RecordingTriggerMode::Toggle,
             language: "auto".to_string(),
             window_opacity: 0.85,
             auto_copy: true,
             max_history: 100,
             theme: "green".to_string(),
             my_memory_key: String::new(),
+            cloud_model: None,
+            cloud_response_format: "json".to_string(),
+            cloud_max_upload_mb: 25,
+            cloud_max_retries: 2,
+            restore_clipboard: false,
+            clipboard_restore_delay_ms: 1500,
+            fallback_to_cloud: false,
+            auto_stop_silence_ms: 0,
+            auto_stop_threshold: 0.01,
+            max_recording_secs: 300,
+            trim_silence_threshold: 0.0,
+            noise_gate_threshold: 0.0,
+            level_attack: default_level_attack(),
+            level_release: default_level_release(),
+            notify_on_complete: false,
+            autostart: false,
+            preserve_channels: false,
+            audio_host: None,
+            input_device: None,
+            pre_roll_ms: 0,
+            min_recording_ms: 300,
+            discard_short_recordings: false,
+            max_segment_len: 0,
+            force_single_segment: None,
+            initial_prompt: String::new(),
+            translate_to_english: false,
+            auto_copy_suffix: "none".to_string(),
+            insert_timestamp_prefix: false,
+            timestamp_prefix_format: default_timestamp_prefix_format(),
+            translate_on_complete: None,
+            always_on_top: true,
+            prefer_english_models: false,
+            unload_after_idle_secs: 0,
+            whisper_threads: None,
+            inference_timeout_cap_secs: crate::commands::transcribe::DEFAULT_INFERENCE_TIMEOUT_CAP_SECS,
+            model_mirror_base: None,
+            http_proxy: None,
+            metrics_endpoint: None,
+            models_dir: None,
         }
     }
 }
@@ -135,13 +361,37 @@ pub struct InnerState {
     pub model_status: ModelStatus,
     pub settings: AppSettings,
     pub history: Vec<HistoryItem>,
-    pub download_progress: f64,
+    /// 各模型的下载状态，key 为模型名（tiny/base/small/medium/large-v3）
+    pub downloads: HashMap<String, DownloadState>,
     /// 录音完成后保存在这里，等待推理消费
     pub audio_buffer: Option<Vec<f32>>,
+    /// 最近一次完成识别的录音（16kHz 单声道），供导出 WAV 使用
+    pub last_recording: Option<Vec<f32>>,
+    /// last_recording 对应的历史记录 ID
+    pub last_recording_id: Option<String>,
+    /// 分声道录音时各声道的 16kHz 数据（与 audio_buffer 同时写入、同时清空）
+    pub channel_buffers: Option<Vec<Vec<f32>>>,
+    /// audio_buffer 对应录音的采集时长对比（与 audio_buffer 同时写入）
+    pub capture_timing: Option<crate::audio::CaptureTiming>,
     /// 今日翻译已用次数（MyMemory API，无 Key 时本地估算）
     pub translation_day_count: u32,
     /// 计数对应的日期（"2024-02-26"），日期变化时自动归零
     pub translation_day_date: String,
+    /// 进入 Processing 状态的时间，供看门狗判断是否卡死
+    pub processing_since: Option<Instant>,
+    /// 连续听写进行中：识别完成后自动重新开始录音
+    pub continuous_active: bool,
+    /// 录音序号，每次开始录音 +1，供后台监视任务判断自己是否过期
+    pub recording_session: u64,
+    /// 有尚未写入磁盘的设置（防抖写入等待中）
+    pub settings_persist_pending: bool,
+    /// 应用正在退出：进行中的下载据此中止并清理不完整文件
+    pub shutting_down: bool,
+    /// 最近一次使用本地模型（加载或识别）的时间，供空闲自动卸载判断
+    pub model_last_used: Option<Instant>,
+    /// 引擎中已加载的模型文件名（与 WhisperEngine 同步的副本）
+    /// 状态查询读这里，不去等推理期间长时间被占用的 whisper 锁
+    pub loaded_model_file: Option<String>,
 }
 
 impl InnerState {
@@ -151,11 +401,42 @@ impl InnerState {
             model_status: ModelStatus::default(),
             settings: AppSettings::default(),
             history: Vec::new(),
-            download_progress: 0.0,
+            downloads: HashMap::new(),
             audio_buffer: None,
+            last_recording: None,
+            last_recording_id: None,
+            channel_buffers: None,
+            capture_timing: None,
             translation_day_count: 0,
             translation_day_date: String::new(),
+            processing_since: None,
+            continuous_active: false,
+            recording_session: 0,
+            settings_persist_pending: false,
+            shutting_down: false,
+            model_last_used: None,
+            loaded_model_file: None,
+        }
+    }
+
+    /// 看门狗：Processing 持续超过 timeout_secs 仍未结束，视为识别流程已丢失，强制回到 Idle
+    ///
+    /// 返回 true 表示发生了强制复位
+    pub fn recover_stuck_processing(&mut self, timeout_secs: u64) -> bool {
+        if self.recording_status != RecordingStatus::Processing {
+            return false;
+        }
+        let stuck = self.processing_since
+            .map(|t| t.elapsed().as_secs() >= timeout_secs)
+            .unwrap_or(true); // 没有时间戳的 Processing 无法判断，直接复位
+        if stuck {
+            self.recording_status = RecordingStatus::Idle;
+            self.audio_buffer = None;
+            self.channel_buffers = None;
+            self.capture_timing = None;
+            self.processing_since = None;
         }
+        stuck
     }
 }
 
@@ -170,12 +451,19 @@ impl InnerState {
 /// - recorder：单独存放，避免持锁时间过长（录音流是长生命周期对象）
 /// - whisper：单独存放，模型加载/推理是耗时 blocking 操作，
 ///   放入独立锁 + spawn_blocking 线程，避免阻塞 tokio 运行时
+///
+/// 锁顺序：持有 whisper 时不获取 inner（推理 / 加载线程只锁 whisper）；
+/// 状态查询命令只短暂锁 inner，绝不等待 whisper，
+/// 需要的模型信息从 inner.loaded_model_file 读取
 pub struct AppState {
     pub inner: Arc<Mutex<InnerState>>,
     /// 独立的录音器锁，与 inner 分开，防止死锁
     pub recorder: Arc<Mutex<AudioRecorder>>,
     /// Whisper 推理引擎，与 inner 分开，推理期间不阻塞状态读写
     pub whisper: Arc<Mutex<WhisperEngine>>,
+    /// 本地推理取消标记：cancel_transcription 置为 true，推理线程通过 abort 回调读取
+    /// 放在锁外，推理期间 whisper 锁被长时间占用也能随时取消
+    pub inference_cancel: Arc<AtomicBool>,
 }
 
 impl AppState {
@@ -184,6 +472,7 @@ impl AppState {
             inner: Arc::new(Mutex::new(InnerState::new())),
             recorder: Arc::new(Mutex::new(AudioRecorder::new())),
             whisper: Arc::new(Mutex::new(WhisperEngine::new())),
+            inference_cancel: Arc::new(AtomicBool::new(false)),
         }
     }
 }
@@ -3,6 +3,7 @@
 
 use tauri::{
     App, Manager, Emitter,
+    image::Image,
     menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::TrayIconBuilder,
 };
@@ -62,13 +63,23 @@ pub fn setup_tray(app: &mut App) -> Result<()> {
 
     // 构建托盘图标
     // 使用叶子 V 形的专属托盘图标（黑色 + 透明背景，macOS template image）
-    let tray_icon = tauri::image::Image::from_bytes(
-        include_bytes!("../../icons/tray-icon.png")
-    ).expect("无法加载托盘图标");
+    // 解码失败时退回应用默认图标（彩色，不作为 template），托盘图标问题不应阻止启动
+    let mut builder = TrayIconBuilder::new();
+    match Image::from_bytes(include_bytes!("../../icons/tray-icon.png")) {
+        Ok(icon) => {
+            builder = builder
+                .icon(icon)
+                .icon_as_template(true);   // macOS：让系统自动适配深/浅色菜单栏
+        }
+        Err(e) => {
+            log::warn!("无法加载托盘图标，改用应用默认图标: {}", e);
+            if let Some(icon) = app.default_window_icon().cloned().map(Image::to_owned) {
+                builder = builder.icon(icon);
+            }
+        }
+    }
 
-    let _tray = TrayIconBuilder::new()
-        .icon(tray_icon)
-        .icon_as_template(true)   // macOS：让系统自动适配深/浅色菜单栏
+    let _tray = builder
         .menu(&menu)
         // 菜单事件处理：用户点击菜单项时触发
         .on_menu_event(|app, event| {
@@ -3,70 +3,383 @@
 // whisper.cpp 是 Whisper 模型的高性能 C++ 实现
 
 use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
 /// 支持的模型大小
 /// 模型越大，识别精度越高，但需要更多内存和计算时间
-#[derive(Debug, Clone)]
+// 变体按模型大小排列，派生的比较顺序即大小顺序
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum WhisperModel {
     Tiny,        // ~39M，最快，精度最低
+    TinyEn,      // ~39M，仅英语
     Base,        // ~74M，快
+    BaseEn,      // ~74M，仅英语
     Small,       // ~244M，平衡（推荐日常使用）
+    SmallEn,     // ~244M，仅英语
     Medium,      // ~769M，慢但准确
+    MediumEn,    // ~769M，仅英语
     LargeV3,     // ~1.5G，最慢最准确
 }
 
 impl WhisperModel {
+    /// 所有支持的模型（按从小到大排列）
+    pub fn all() -> Vec<WhisperModel> {
+        vec![
+            WhisperModel::Tiny,
+            WhisperModel::TinyEn,
+            WhisperModel::Base,
+            WhisperModel::BaseEn,
+            WhisperModel::Small,
+            WhisperModel::SmallEn,
+            WhisperModel::Medium,
+            WhisperModel::MediumEn,
+            WhisperModel::LargeV3,
+        ]
+    }
+
     /// 模型文件名（下载时使用）
     pub fn filename(&self) -> &str {
         match self {
             WhisperModel::Tiny => "ggml-tiny.bin",
+            WhisperModel::TinyEn => "ggml-tiny.en.bin",
             WhisperModel::Base => "ggml-base.bin",
+            WhisperModel::BaseEn => "ggml-base.en.bin",
             WhisperModel::Small => "ggml-small.bin",
+            WhisperModel::SmallEn => "ggml-small.en.bin",
             WhisperModel::Medium => "ggml-medium.bin",
+            WhisperModel::MediumEn => "ggml-medium.en.bin",
             WhisperModel::LargeV3 => "ggml-large-v3.bin",
         }
     }
 
+    /// 模型名称（与前端、设置中使用的名称一致）
+    pub fn name(&self) -> &str {
+        match self {
+            WhisperModel::Tiny => "tiny",
+            WhisperModel::TinyEn => "tiny.en",
+            WhisperModel::Base => "base",
+            WhisperModel::BaseEn => "base.en",
+            WhisperModel::Small => "small",
+            WhisperModel::SmallEn => "small.en",
+            WhisperModel::Medium => "medium",
+            WhisperModel::MediumEn => "medium.en",
+            WhisperModel::LargeV3 => "large-v3",
+        }
+    }
+
     /// 模型下载 URL（Hugging Face 镜像）
     pub fn download_url(&self) -> String {
-        // 使用 Hugging Face 的 ggml 格式模型
-        let base = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
-        format!("{}/{}", base, self.filename())
+        // 使用 Hugging Face 的 ggml 格式模型（设置了镜像时走镜像站，路径结构相同）
+        self.download_url_from(&model_host_base())
+    }
+
+    /// 指定站点上的模型下载 URL（镜像站与 Hugging Face 路径结构相同）
+    pub fn download_url_from(&self, host_base: &str) -> String {
+        format!(
+            "{}/ggerganov/whisper.cpp/resolve/main/{}",
+            host_base, self.filename()
+        )
+    }
+
+    /// 模型文件的 SHA256（Hugging Face ggerganov/whisper.cpp 仓库 main 分支）
+    ///
+    /// 写死在代码里，不信任下载站点返回的摘要：镜像损坏或被篡改时无法为自己的文件作保
+    pub fn expected_sha256(&self) -> &str {
+        match self {
+            WhisperModel::Tiny => "be07e048e1e599ad46341c8d2a135645097a538221678b7acdd1b1919c6e1b21",
+            WhisperModel::TinyEn => "921e4cf8686fdd993dcd081a5da5b6c365bfde1162e72b08d75ac75289920b1f",
+            WhisperModel::Base => "60ed5bc3dd14eea856493d334349b405782ddcaf0028d4b5df4088345fba2efe",
+            WhisperModel::BaseEn => "a03779c86df3323075f5e796cb2ce5029f00ec8869eee3fdfb897afe36c6d002",
+            WhisperModel::Small => "1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b",
+            WhisperModel::SmallEn => "c6138d6d58ecc8322097e0f987c32f1be8bb0a18532a3f88f734d1bbf9c41e5d",
+            WhisperModel::Medium => "6c14d5adee5f86394037b4e4e8b59f1673b6cee10e3cf0b11bbdbee79c156208",
+            WhisperModel::MediumEn => "cc37e93478338ec7700281a7ac30a10128929eb8f427dda2e865faa8f6da4356",
+            WhisperModel::LargeV3 => "64d182b440b98d5203c4f9bd541544d84c605196c4f7b845dfa11fb23594d1e2",
+        }
     }
 
     /// 显示名称
     pub fn display_name(&self) -> &str {
         match self {
             WhisperModel::Tiny => "Tiny (~39MB)",
+            WhisperModel::TinyEn => "Tiny.en (~39MB，仅英语)",
             WhisperModel::Base => "Base (~74MB)",
+            WhisperModel::BaseEn => "Base.en (~74MB，仅英语)",
             WhisperModel::Small => "Small (~244MB)",
+            WhisperModel::SmallEn => "Small.en (~244MB，仅英语)",
             WhisperModel::Medium => "Medium (~769MB)",
+            WhisperModel::MediumEn => "Medium.en (~769MB，仅英语)",
             WhisperModel::LargeV3 => "Large-v3 (~1.5GB)",
         }
     }
 
+    /// 预估文件大小（MB），未下载时用于展示和磁盘空间预检
+    pub fn approx_size_mb(&self) -> f64 {
+        match self {
+            WhisperModel::Tiny | WhisperModel::TinyEn => 39.0,
+            WhisperModel::Base | WhisperModel::BaseEn => 74.0,
+            WhisperModel::Small | WhisperModel::SmallEn => 244.0,
+            WhisperModel::Medium | WhisperModel::MediumEn => 769.0,
+            WhisperModel::LargeV3 => 1550.0,
+        }
+    }
+
+    /// 加载后预估占用内存（MB），参考 whisper.cpp 官方数据
+    pub fn required_ram_mb(&self) -> u64 {
+        match self {
+            WhisperModel::Tiny | WhisperModel::TinyEn => 273,
+            WhisperModel::Base | WhisperModel::BaseEn => 388,
+            WhisperModel::Small | WhisperModel::SmallEn => 852,
+            WhisperModel::Medium | WhisperModel::MediumEn => 2100,
+            WhisperModel::LargeV3 => 3900,
+        }
+    }
+
+    /// CPU 推理的保守实时率估算（推理耗时 / 音频时长），用于计算推理超时
+    /// GPU 加速时实际会快得多，这里按较慢的 CPU 情况取值
+    pub fn cpu_realtime_factor(&self) -> f64 {
+        match self {
+            WhisperModel::Tiny | WhisperModel::TinyEn => 0.5,
+            WhisperModel::Base | WhisperModel::BaseEn => 0.8,
+            WhisperModel::Small | WhisperModel::SmallEn => 2.0,
+            WhisperModel::Medium | WhisperModel::MediumEn => 5.0,
+            WhisperModel::LargeV3 => 10.0,
+        }
+    }
+
     /// 从字符串解析模型名
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "tiny" => Some(WhisperModel::Tiny),
+            "tiny.en" | "tiny-en" | "tiny_en" => Some(WhisperModel::TinyEn),
             "base" => Some(WhisperModel::Base),
+            "base.en" | "base-en" | "base_en" => Some(WhisperModel::BaseEn),
             "small" => Some(WhisperModel::Small),
+            "small.en" | "small-en" | "small_en" => Some(WhisperModel::SmallEn),
             "medium" => Some(WhisperModel::Medium),
+            "medium.en" | "medium-en" | "medium_en" => Some(WhisperModel::MediumEn),
             "large-v3" | "large_v3" | "largev3" => Some(WhisperModel::LargeV3),
             _ => None,
         }
     }
+
+    /// 是否为仅英语的 .en 模型（只能识别英语，但英语识别更快更准）
+    pub fn is_english_only(&self) -> bool {
+        matches!(
+            self,
+            WhisperModel::TinyEn | WhisperModel::BaseEn | WhisperModel::SmallEn | WhisperModel::MediumEn
+        )
+    }
+
+    /// 同尺寸的仅英语模型（Large-v3 没有 .en 版本）
+    pub fn english_variant(&self) -> Option<WhisperModel> {
+        match self {
+            WhisperModel::Tiny | WhisperModel::TinyEn => Some(WhisperModel::TinyEn),
+            WhisperModel::Base | WhisperModel::BaseEn => Some(WhisperModel::BaseEn),
+            WhisperModel::Small | WhisperModel::SmallEn => Some(WhisperModel::SmallEn),
+            WhisperModel::Medium | WhisperModel::MediumEn => Some(WhisperModel::MediumEn),
+            WhisperModel::LargeV3 => None,
+        }
+    }
 }
 
-/// 获取模型存储目录
+/// 本地识别实际使用的模型
+///
+/// 识别语言为英语且开启 prefer_english 时，改用同尺寸的 .en 模型（需已下载，否则仍用原模型）
+pub fn resolve_local_model(name: &str, language: &str, prefer_english: bool) -> Option<WhisperModel> {
+    let model = WhisperModel::from_str(name)?;
+    if !prefer_english || language != "en" || model.is_english_only() {
+        return Some(model);
+    }
+    match model.english_variant() {
+        Some(en) if is_model_downloaded(&en) => Some(en),
+        _ => Some(model),
+    }
+}
+
+/// whisper.cpp 支持的语言（代码, 英文名），顺序与 whisper 语言 ID 一致
+pub const SUPPORTED_LANGUAGES: &[(&str, &str)] = &[
+    ("en", "english"), ("zh", "chinese"), ("de", "german"), ("es", "spanish"),
+    ("ru", "russian"), ("ko", "korean"), ("fr", "french"), ("ja", "japanese"),
+    ("pt", "portuguese"), ("tr", "turkish"), ("pl", "polish"), ("ca", "catalan"),
+    ("nl", "dutch"), ("ar", "arabic"), ("sv", "swedish"), ("it", "italian"),
+    ("id", "indonesian"), ("hi", "hindi"), ("fi", "finnish"), ("vi", "vietnamese"),
+    ("he", "hebrew"), ("uk", "ukrainian"), ("el", "greek"), ("ms", "malay"), ("cs", "czech"),
+    ("ro", "romanian"), ("da", "danish"), ("hu", "hungarian"), ("ta", "tamil"),
+    ("no", "norwegian"), ("th", "thai"), ("ur", "urdu"), ("hr", "croatian"),
+    ("bg", "bulgarian"), ("lt", "lithuanian"), ("la", "latin"), ("mi", "maori"),
+    ("ml", "malayalam"), ("cy", "welsh"), ("sk", "slovak"), ("te", "telugu"), ("fa", "persian"),
+    ("lv", "latvian"), ("bn", "bengali"), ("sr", "serbian"), ("az", "azerbaijani"),
+    ("sl", "slovenian"), ("kn", "kannada"), ("et", "estonian"), ("mk", "macedonian"),
+    ("br", "breton"), ("eu", "basque"), ("is", "icelandic"), ("hy", "armenian"),
+    ("ne", "nepali"), ("mn", "mongolian"), ("bs", "bosnian"), ("kk", "kazakh"),
+    ("sq", "albanian"), ("sw", "swahili"), ("gl", "galician"), ("mr", "marathi"),
+    ("pa", "punjabi"), ("si", "sinhala"), ("km", "khmer"), ("sn", "shona"), ("yo", "yoruba"),
+    ("so", "somali"), ("af", "afrikaans"), ("oc", "occitan"), ("ka", "georgian"),
+    ("be", "belarusian"), ("tg", "tajik"), ("sd", "sindhi"), ("gu", "gujarati"),
+    ("am", "amharic"), ("yi", "yiddish"), ("lo", "lao"), ("uz", "uzbek"), ("fo", "faroese"),
+    ("ht", "haitian creole"), ("ps", "pashto"), ("tk", "turkmen"), ("nn", "nynorsk"),
+    ("mt", "maltese"), ("sa", "sanskrit"), ("lb", "luxembourgish"), ("my", "myanmar"),
+    ("bo", "tibetan"), ("tl", "tagalog"), ("mg", "malagasy"), ("as", "assamese"),
+    ("tt", "tatar"), ("haw", "hawaiian"), ("ln", "lingala"), ("ha", "hausa"), ("ba", "bashkir"),
+    ("jw", "javanese"), ("su", "sundanese"), ("yue", "cantonese"),
+];
+
+/// 语言代码是否有效（"auto" 表示自动检测）
+pub fn is_supported_language(code: &str) -> bool {
+    code == "auto" || SUPPORTED_LANGUAGES.iter().any(|(c, _)| *c == code)
+}
+
+/// 各语言建议的最小模型（语言码, 中文名, 最小模型）
+/// 小模型对中日韩、声调语言等识别较差；未列出的语言不做提示
+pub const LANGUAGE_MIN_MODELS: &[(&str, &str, WhisperModel)] = &[
+    ("auto", "自动检测", WhisperModel::Base),
+    ("zh",   "中文",     WhisperModel::Small),
+    ("yue",  "粤语",     WhisperModel::Small),
+    ("ja",   "日语",     WhisperModel::Small),
+    ("ko",   "韩语",     WhisperModel::Small),
+    ("th",   "泰语",     WhisperModel::Small),
+    ("vi",   "越南语",   WhisperModel::Small),
+    ("ar",   "阿拉伯语", WhisperModel::Small),
+    ("hi",   "印地语",   WhisperModel::Small),
+];
+
+/// 语言与模型搭配的建议：仅英语模型用于其他语言、或模型低于该语言建议的最小模型时返回提示文字
+pub fn language_model_advice(language: &str, model: &WhisperModel) -> Option<String> {
+    if model.is_english_only() && language != "en" {
+        return Some(format!(
+            "{} 仅支持英语，识别语言为 {} 时请换用多语言模型",
+            model.display_name(), language
+        ));
+    }
+    let (_, label, min_model) = LANGUAGE_MIN_MODELS.iter()
+        .find(|(code, _, _)| *code == language)?;
+    if model >= min_model {
+        return None;
+    }
+    Some(format!(
+        "{} 对{}识别较差，建议 {} 及以上",
+        model.display_name(), label, min_model.display_name()
+    ))
+}
+
+/// ggml 模型文件头魔数（小端 u32 0x67676d6c，即字节 "lmgg"）
+const GGML_FILE_MAGIC: [u8; 4] = [0x6c, 0x6d, 0x67, 0x67];
+
+/// 校验文件是否像一个完整的 ggml 模型：大小不低于 1MB 且文件头魔数正确
+/// 返回文件大小（字节）
+pub fn validate_ggml_file(path: &Path) -> Result<u64> {
+    use std::io::Read;
+
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("无法读取文件: {:?}", path))?
+        .len();
+    if size < 1024 * 1024 {
+        anyhow::bail!("文件过小 ({} bytes)，不是有效的模型文件", size);
+    }
+
+    let mut magic = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .context("读取文件头失败")?;
+    if magic != GGML_FILE_MAGIC {
+        anyhow::bail!("文件头不是 ggml 格式，请选择 whisper.cpp 使用的 ggml-*.bin 模型");
+    }
+    Ok(size)
+}
+
+/// 默认模型下载站点
+pub const DEFAULT_MODEL_HOST: &str = "https://huggingface.co";
+
+/// 内置的备用下载站点：主站点连接失败 / 超时时依次尝试
+pub const FALLBACK_MODEL_HOSTS: &[&str] = &["https://hf-mirror.com"];
+
+/// 用户设置的模型下载镜像（来自 settings.model_mirror_base，如 https://hf-mirror.com）
+/// 与 MODELS_DIR_OVERRIDE 相同，download_url 是无状态方法，覆盖值放在全局
+static MODEL_MIRROR_OVERRIDE: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+
+/// 校验模型镜像地址：需为带主机名的 http(s) URL（如 https://hf-mirror.com）
+pub fn validate_model_mirror(base: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(base.trim())
+        .map_err(|e| format!("模型镜像地址无效: {}（{}）", base, e))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(format!("模型镜像地址无效: {}（需以 http:// 或 https:// 开头）", base));
+    }
+    Ok(())
+}
+
+/// 设置（或清除）模型下载镜像，空字符串视为清除
+/// 地址无效时（如手动修改过设置文件）记录警告并使用默认站点
+pub fn set_model_mirror_override(base: Option<String>) {
+    let base = base
+        .map(|b| b.trim().trim_end_matches('/').to_string())
+        .filter(|b| !b.is_empty())
+        .filter(|b| match validate_model_mirror(b) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("{}，使用默认站点 {}", e, DEFAULT_MODEL_HOST);
+                false
+            }
+        });
+    if let Ok(mut guard) = MODEL_MIRROR_OVERRIDE.write() {
+        *guard = base;
+    }
+}
+
+/// 当前使用的模型下载站点（镜像优先）
+pub fn model_host_base() -> String {
+    MODEL_MIRROR_OVERRIDE.read()
+        .ok()
+        .and_then(|guard| guard.clone())
+        .unwrap_or_else(|| DEFAULT_MODEL_HOST.to_string())
+}
+
+/// 下载时依次尝试的站点：用户镜像（如有）→ Hugging Face → 内置备用镜像（去重）
+pub fn model_host_candidates() -> Vec<String> {
+    let mut hosts = vec![model_host_base(), DEFAULT_MODEL_HOST.to_string()];
+    hosts.extend(FALLBACK_MODEL_HOSTS.iter().map(|h| h.to_string()));
+    let mut seen = std::collections::HashSet::new();
+    hosts.retain(|h| seen.insert(h.clone()));
+    hosts
+}
+
+/// 用户自定义的模型目录（来自 settings.models_dir）
+/// get_models_dir 是无状态函数，被下载/加载/删除等多处调用，
+/// 所以覆盖值放在全局，由设置加载/保存时同步
+static MODELS_DIR_OVERRIDE: Lazy<RwLock<Option<PathBuf>>> = Lazy::new(|| RwLock::new(None));
+
+/// 设置（或清除）自定义模型目录
+pub fn set_models_dir_override(dir: Option<PathBuf>) {
+    if let Ok(mut guard) = MODELS_DIR_OVERRIDE.write() {
+        *guard = dir;
+    }
+}
+
+/// 默认模型存储目录
 /// macOS/Linux: ~/.local/share/voxie/models/
 /// Windows: %LOCALAPPDATA%\voxie\models\
-pub fn get_models_dir() -> Result<PathBuf> {
+pub fn default_models_dir() -> Result<PathBuf> {
     let base_dir = dirs::data_local_dir()
         .context("无法获取用户数据目录")?;
-    let models_dir = base_dir.join("voxie").join("models");
+    Ok(base_dir.join("voxie").join("models"))
+}
+
+/// 获取模型存储目录
+/// 设置了自定义目录时优先使用，否则使用默认目录
+pub fn get_models_dir() -> Result<PathBuf> {
+    let override_dir = MODELS_DIR_OVERRIDE.read()
+        .ok()
+        .and_then(|guard| guard.clone());
+
+    let models_dir = match override_dir {
+        Some(dir) => dir,
+        None => default_models_dir()?,
+    };
 
     // 如果目录不存在则创建
     if !models_dir.exists() {
@@ -77,6 +390,22 @@ pub fn get_models_dir() -> Result<PathBuf> {
     Ok(models_dir)
 }
 
+/// 校验自定义模型目录：不能是文件，不存在则创建，且必须可写
+pub fn validate_models_dir(dir: &Path) -> Result<()> {
+    if dir.is_file() {
+        anyhow::bail!("所选路径是文件而不是目录: {:?}", dir);
+    }
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("无法创建模型目录: {:?}", dir))?;
+
+    // 写入探测文件确认目录可写
+    let probe = dir.join(".voxie-write-test");
+    std::fs::write(&probe, b"ok")
+        .with_context(|| format!("模型目录不可写: {:?}", dir))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
 /// 获取指定模型的文件路径
 pub fn get_model_path(model: &WhisperModel) -> Result<PathBuf> {
     let models_dir = get_models_dir()?;
@@ -91,6 +420,15 @@ pub fn is_model_downloaded(model: &WhisperModel) -> bool {
     }
 }
 
+/// 查询系统当前可用内存（MB）
+///
+/// 部分平台 / 容器环境无法读取内存信息，此时返回 0，调用方应视为"未知"而不是"没有可用内存"
+pub fn available_memory_mb() -> u64 {
+    let mut sys = sysinfo::System::new();
+    sys.refresh_memory();
+    sys.available_memory() / 1024 / 1024
+}
+
 /// 计算音频数据的 RMS 音量（用于检测静音）
 pub fn audio_rms(data: &[f32]) -> f32 {
     if data.is_empty() {
@@ -100,10 +438,35 @@ pub fn audio_rms(data: &[f32]) -> f32 {
     (sum_sq / data.len() as f64).sqrt() as f32
 }
 
+/// 用户设置的推理线程数（来自 settings.whisper_threads，None = 自动）
+/// 与 MODEL_MIRROR_OVERRIDE 相同，推理时无法访问设置，覆盖值放在全局
+static THREADS_OVERRIDE: Lazy<RwLock<Option<i32>>> = Lazy::new(|| RwLock::new(None));
+
+/// 设置（或清除）推理线程数覆盖
+pub fn set_threads_override(threads: Option<i32>) {
+    if let Ok(mut guard) = THREADS_OVERRIDE.write() {
+        *guard = threads;
+    }
+}
+
+/// 实际使用的推理线程数：设置了覆盖值时使用它（限制在 1..=逻辑核心数），否则自动选择
+pub fn inference_threads() -> i32 {
+    let override_threads = THREADS_OVERRIDE.read().ok().and_then(|guard| *guard);
+    match override_threads {
+        Some(threads) => {
+            let logical = num_cpus::get() as i32;
+            let threads = threads.clamp(1, logical.max(1));
+            log::info!("CPU: {} 逻辑核 → 使用 {} 线程（用户设置）", logical, threads);
+            threads
+        }
+        None => recommended_threads(),
+    }
+}
+
 /// 获取推荐的线程数
 /// Windows CPU 模式下使用物理核心数（避免超线程争用导致挂死）
 /// macOS 使用逻辑核心数（Metal GPU 加速为主，CPU 线程影响较小）
-fn recommended_threads() -> i32 {
+pub fn recommended_threads() -> i32 {
     let physical = num_cpus::get_physical() as i32;
     let logical = num_cpus::get() as i32;
 
@@ -126,6 +489,60 @@ fn recommended_threads() -> i32 {
     threads
 }
 
+/// 推理被用户取消时的错误信息
+pub const INFERENCE_CANCELLED: &str = "已取消";
+
+/// 带时间戳的识别段落
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    pub text: String,
+    /// 段落起止时间（毫秒，相对录音开头）
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// 段落解码完成时的回调（在推理线程中调用，用于流式显示部分结果）
+pub type SegmentCallback = Box<dyn FnMut(Segment) + Send>;
+
+/// 把段落文本拼接为完整识别结果（去除首尾空格）
+pub fn join_segments(segments: &[Segment]) -> String {
+    segments.iter()
+        .map(|s| s.text.as_str())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// 初始提示词最大字符数
+/// whisper.cpp 只保留提示词最后 n_text_ctx/2（224）个 token，过长的提示词前半部分会被静默丢弃，
+/// 这里按字符数截断并记录日志（中文约 1~2 token / 字，英文约 4 字符 / token）
+const MAX_INITIAL_PROMPT_CHARS: usize = 200;
+
+/// 识别结果的分段控制和解码提示
+#[derive(Debug, Clone, Default)]
+pub struct SegmentOptions {
+    /// 每段最大字符数（0 = 不限制）；启用时同时开启 token 时间戳
+    pub max_len: u32,
+    /// 强制单段输出：Some(true) 总是单段，Some(false) 从不单段，None 按时长自动判断
+    pub force_single_segment: Option<bool>,
+    /// 初始提示词（空 = 不使用），超过 MAX_INITIAL_PROMPT_CHARS 时截断
+    pub initial_prompt: String,
+    /// 翻译成英文输出（false = 按原语言转录）
+    pub translate: bool,
+}
+
+/// 当前平台编译启用的推理后端（用于诊断和性能测试报告）
+pub fn compute_backend() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Metal"
+    } else if cfg!(target_os = "windows") {
+        "CUDA（无可用 NVIDIA 显卡时回退 CPU）"
+    } else {
+        "CPU"
+    }
+}
+
 /// Whisper 识别引擎
 /// 封装了 WhisperContext 的生命周期管理
 pub struct WhisperEngine {
@@ -148,7 +565,18 @@ impl WhisperEngine {
     /// 加载 Whisper 模型
     /// model_path: 模型文件的完整路径
     pub fn load_model(&mut self, model_path: &Path) -> Result<()> {
+        self.load_model_with_phases(model_path, |_| {})
+    }
+
+    /// 加载 Whisper 模型，并在各阶段回调 on_phase
+    /// 阶段："reading"（校验模型文件）→ "initializing"（whisper.cpp 创建上下文）
+    pub fn load_model_with_phases(
+        &mut self,
+        model_path: &Path,
+        on_phase: impl Fn(&str),
+    ) -> Result<()> {
         log::info!("开始加载 Whisper 模型: {:?}", model_path);
+        on_phase("reading");
 
         if !model_path.exists() {
             anyhow::bail!("模型文件不存在: {:?}", model_path);
@@ -185,6 +613,14 @@ impl WhisperEngine {
         // 创建 Whisper 上下文（这一步会加载模型权重到内存/VRAM）
         let path_str = model_path.to_str().context("模型路径包含无效字符")?;
         log::info!("调用 whisper.cpp 加载模型，路径: {}", path_str);
+        on_phase("initializing");
+
+        // 先释放旧模型，避免新旧两个上下文同时占用内存（大模型可达数 GB）
+        // 代价是新模型加载失败时旧模型也已卸载
+        if self.ctx.is_some() {
+            log::info!("释放已加载的模型: {:?}", self.current_model);
+            self.unload();
+        }
 
         let ctx = WhisperContext::new_with_params(path_str, params)
             .context("加载 Whisper 模型失败，请检查模型文件是否完整")?;
@@ -207,6 +643,35 @@ impl WhisperEngine {
     /// language: 语言代码 ("zh", "en", "auto" 等)
     /// 返回识别文本
     pub fn transcribe(&self, audio_data: &[f32], language: &str) -> Result<String> {
+        self.transcribe_with_language(audio_data, language, &SegmentOptions::default(), None)
+            .map(|(text, _)| text)
+    }
+
+    /// 执行语音识别，同时返回实际使用的语言
+    /// 指定语言时返回该语言；auto 模式返回 whisper 检测到的语言（检测失败为 None）
+    /// segments: 分段控制（最大段长 / 单段模式）
+    /// cancel: 取消标记，推理过程中置为 true 时 whisper.cpp 尽快中止，返回 INFERENCE_CANCELLED 错误
+    pub fn transcribe_with_language(
+        &self,
+        audio_data: &[f32],
+        language: &str,
+        segments: &SegmentOptions,
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> Result<(String, Option<String>)> {
+        let (segments, detected_language) = self.transcribe_with_segments(audio_data, language, segments, cancel, None)?;
+        Ok((join_segments(&segments), detected_language))
+    }
+
+    /// 执行语音识别，返回带起止时间的段落和实际使用的语言（参数同 transcribe_with_language）
+    /// on_segment: 每解码完一个段落立即回调（推理仍在进行），最终结果仍以返回值为准
+    pub fn transcribe_with_segments(
+        &self,
+        audio_data: &[f32],
+        language: &str,
+        segments: &SegmentOptions,
+        cancel: Option<Arc<AtomicBool>>,
+        on_segment: Option<SegmentCallback>,
+    ) -> Result<(Vec<Segment>, Option<String>)> {
         let ctx = self.ctx.as_ref()
             .context("Whisper 模型未加载，请先加载模型")?;
 
@@ -245,7 +710,7 @@ impl WhisperEngine {
         }
 
         // 性能优化参数
-        let n_threads = recommended_threads();
+        let n_threads = inference_threads();
         params.set_n_threads(n_threads);
 
         // 禁用不必要的输出
@@ -255,12 +720,58 @@ impl WhisperEngine {
         params.set_print_timestamps(false);
 
         // 翻译模式：false 表示转录（保持原语言），true 表示翻译成英文
-        params.set_translate(false);
+        params.set_translate(segments.translate);
+        if segments.translate {
+            log::info!("翻译模式: 输出英文译文");
+        }
 
-        // 短音频优化：5 秒以下使用单段模式，减少开销
-        if audio_duration_s < 5.0 {
+        // 单段模式：未配置时沿用短音频优化（5 秒以下使用单段模式，减少开销）
+        let single_segment = segments.force_single_segment
+            .unwrap_or(audio_duration_s < 5.0);
+        if single_segment {
             params.set_single_segment(true);
-            log::info!("短音频模式: 启用 single_segment");
+            log::info!("单段模式: 启用 single_segment");
+        }
+
+        // 最大段长：whisper.cpp 依赖 token 时间戳来切分段落
+        if segments.max_len > 0 {
+            params.set_token_timestamps(true);
+            params.set_max_len(segments.max_len as i32);
+            params.set_split_on_word(true);
+            log::info!("最大段长: {} 字符", segments.max_len);
+        }
+
+        // 初始提示词：作为前文上下文，让专有名词按提示的写法输出
+        let prompt = segments.initial_prompt.trim();
+        let prompt_chars = prompt.chars().count();
+        let prompt = if prompt_chars > MAX_INITIAL_PROMPT_CHARS {
+            log::warn!(
+                "初始提示词过长（{} 字符），已截断为前 {} 字符",
+                prompt_chars, MAX_INITIAL_PROMPT_CHARS
+            );
+            prompt.chars().take(MAX_INITIAL_PROMPT_CHARS).collect::<String>()
+        } else {
+            prompt.to_string()
+        };
+        if !prompt.is_empty() {
+            params.set_initial_prompt(&prompt);
+            log::info!("初始提示词: {} 字符", prompt.chars().count());
+        }
+
+        // 取消：whisper.cpp 在解码步骤之间调用 abort 回调，返回 true 即中止
+        if let Some(flag) = cancel.clone() {
+            params.set_abort_callback_safe(move || flag.load(Ordering::Relaxed));
+        }
+
+        // 部分结果：whisper.cpp 每解码出新段落调用一次（时间戳单位为 10 毫秒）
+        if let Some(mut on_segment) = on_segment {
+            params.set_segment_callback_safe(move |data: whisper_rs::SegmentCallbackData| {
+                on_segment(Segment {
+                    text: data.text,
+                    start_ms: data.start_timestamp * 10,
+                    end_ms: data.end_timestamp * 10,
+                });
+            });
         }
 
         log::info!(
@@ -277,8 +788,12 @@ impl WhisperEngine {
 
         // 执行完整推理（这是最耗时的步骤）
         // Windows CPU 模式下可能非常慢，外层有超时保护
-        state.full(params, audio_data)
-            .context("Whisper 识别失败")?;
+        let full_result = state.full(params, audio_data);
+        if cancel.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+            log::info!("Whisper 推理已取消（{:.1}秒）", start_time.elapsed().as_secs_f64());
+            anyhow::bail!(INFERENCE_CANCELLED);
+        }
+        full_result.context("Whisper 识别失败")?;
 
         let elapsed = start_time.elapsed();
         log::info!("Whisper 推理完成，耗时: {:.1}秒", elapsed.as_secs_f64());
@@ -289,25 +804,39 @@ impl WhisperEngine {
 
         log::info!("识别结果: {} 个段落", n_segments);
 
-        let mut result = String::new();
+        // 实际语言：auto 模式下取 whisper 检测结果（ID 与 SUPPORTED_LANGUAGES 顺序一致）
+        let detected_language = if language == "auto" || language.is_empty() {
+            state.full_lang_id_from_state()
+                .ok()
+                .and_then(|id| usize::try_from(id).ok())
+                .and_then(|id| SUPPORTED_LANGUAGES.get(id))
+                .map(|(code, _)| code.to_string())
+        } else {
+            Some(language.to_string())
+        };
+
+        // 时间戳单位为 10 毫秒
+        let mut result = Vec::with_capacity(n_segments.max(0) as usize);
         for i in 0..n_segments {
-            let segment_text = state.full_get_segment_text(i)
+            let text = state.full_get_segment_text(i)
                 .context(format!("获取第 {} 段文本失败", i))?;
-            result.push_str(&segment_text);
+            let t0 = state.full_get_segment_t0(i)
+                .context(format!("获取第 {} 段起始时间失败", i))?;
+            let t1 = state.full_get_segment_t1(i)
+                .context(format!("获取第 {} 段结束时间失败", i))?;
+            result.push(Segment { text, start_ms: t0 * 10, end_ms: t1 * 10 });
         }
 
-        // 清理文本：去除首尾空格
-        let result = result.trim().to_string();
-
-        // 按字符截取预览，避免在 UTF-8 多字节字符中间切割导致 panic（中文 = 3 字节/字符）
-        let preview: String = result.chars().take(50).collect();
+        let text = join_segments(&result);
+        let preview = crate::truncate_chars(&text, 50);
         log::info!(
-            "识别完成: \"{}\" (耗时 {:.1}秒, 实时率 {:.1}x)",
+            "识别完成: \"{}\" ({} 段, 耗时 {:.1}秒, 实时率 {:.1}x)",
             preview,
+            result.len(),
             elapsed.as_secs_f64(),
             elapsed.as_secs_f64() / audio_duration_s as f64,
         );
-        Ok(result)
+        Ok((result, detected_language))
     }
 
     /// 检查模型是否已加载
@@ -331,3 +860,19 @@ impl WhisperEngine {
 // 让 WhisperEngine 可以在线程间传递
 // WhisperContext 在 whisper-rs 中已标记为 Send
 unsafe impl Send for WhisperEngine {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_model_has_a_distinct_sha256() {
+        let mut seen = std::collections::HashSet::new();
+        for model in WhisperModel::all() {
+            let hash = model.expected_sha256();
+            assert_eq!(hash.len(), 64, "{}", model.name());
+            assert!(hash.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)), "{}", model.name());
+            assert!(seen.insert(hash), "{} 的摘要与其他模型重复", model.name());
+        }
+    }
+}
@@ -22,7 +22,7 @@ async function tauriInvoke<T>(cmd: string, args?: Record<string, unknown>): Prom
 export type RecordingStatus = 'idle' | 'recording' | 'processing'
 export type ModelStatus = 'notDownloaded' | 'downloading' | 'downloaded' | 'loading' | 'ready' | { error: string }
 export type TranscriptionMode = 'local' | 'cloud'
-export type CloudProvider = 'openAI' | 'aliyun' | 'custom'
+export type CloudProvider = 'openAI' | 'aliyun' | 'groq' | 'deepgram' | 'azure' | 'custom'
 export type AppTheme = 'green' | 'blue' | 'violet' | 'ember' | 'sand' | 'white' | 'gray'
 
 /** 将主题应用到 <html> 的 data-theme 属性 */
@@ -41,6 +41,7 @@ export interface HistoryItem {
   durationMs: number
   mode: TranscriptionMode
   modelName?: string
+  pinned?: boolean
 }
 
 export interface AppSettings {
@@ -127,7 +128,7 @@ export const useAppStore = defineStore('app', () => {
     cloudProvider: 'openAI',
     cloudBaseUrl: 'https://api.openai.com/v1',
     cloudApiKey: '',
-    shortcutKey: 'Alt',
+    shortcutKey: 'Alt+Space',
     language: 'auto',
     windowOpacity: 0.85,
     autoCopy: true,
@@ -198,13 +199,14 @@ export const useAppStore = defineStore('app', () => {
 
   async function clearHistory() {
     if (!isTauri) {
-      history.value = []
+      history.value = history.value.filter(item => item.pinned)
       showToast('历史记录已清空', 'success')
       return
     }
     try {
       await tauriInvoke('clear_history')
-      history.value = []
+      // 后端保留置顶记录，本地同步
+      history.value = history.value.filter(item => item.pinned)
       showToast('历史记录已清空', 'success')
     } catch (e) {
       console.error('清空历史记录失败:', e)
@@ -263,9 +265,10 @@ export const useAppStore = defineStore('app', () => {
         showToast('正在识别中，请稍候...', 'info')
       }
 
-      const result = await tauriInvoke<{ text: string; durationMs: number; itemId: string }>('transcribe_audio')
-      if (settings.value.autoCopy && result.text) {
-        await copyToClipboard(result.text)
+      // 自动复制由后端在识别完成时完成（快捷键 / 自动停止等触发方式同样生效）
+      const result = await tauriInvoke<{ text: string; durationMs: number; itemId: string; clipboardText: string; copied: boolean }>('transcribe_audio')
+      if (result.copied) {
+        showToast('已复制到剪贴板', 'success')
       }
       recordingStatus.value = 'idle'
       await loadHistory()
@@ -302,6 +305,43 @@ export const useAppStore = defineStore('app', () => {
     }
   }
 
+  // ===== 后端事件 =====
+  // 快捷键、静音自动停止、时长上限、连续听写由 Rust 侧直接开始 / 停止并识别（含自动复制），
+  // 不经过 startRecording / stopRecording，这里同步录音状态，避免界面停留在旧状态
+  async function listenBackendEvents() {
+    if (!isTauri) return
+    const { listen } = await import('@tauri-apps/api/event')
+    await listen('shortcut-recording-started', () => {
+      recordingStatus.value = 'recording'
+    })
+    await listen<{ discarded: boolean }>('shortcut-recording-stopped', (event) => {
+      recordingStatus.value = event.payload.discarded ? 'idle' : 'processing'
+    })
+    await listen<string>('shortcut-recording-error', (event) => {
+      recordingStatus.value = 'idle'
+      showToast(`录音失败: ${event.payload}`, 'error')
+    })
+    // 静音自动停止 / 达到时长上限：payload 同 shortcut-recording-stopped，未被丢弃时后端继续识别
+    for (const name of ['recording-auto-stopped', 'recording-limit-reached']) {
+      await listen<{ discarded: boolean }>(name, (event) => {
+        recordingStatus.value = event.payload.discarded ? 'idle' : 'processing'
+      })
+    }
+    // 连续听写自动开始下一句
+    await listen('recording-restarted', () => {
+      recordingStatus.value = 'recording'
+    })
+    await listen('new-transcription', () => {
+      recordingStatus.value = 'idle'
+    })
+    await listen('transcription-failed', () => {
+      recordingStatus.value = 'idle'
+    })
+    await listen<string>('config-warning', (event) => {
+      showToast(event.payload, 'info')
+    })
+  }
+
   function showToast(message: string, type: 'success' | 'error' | 'info' = 'info') {
     toast.value = { message, type }
     setTimeout(() => { toast.value = null }, 3000)
@@ -430,7 +470,7 @@ export const useAppStore = defineStore('app', () => {
     history, settings, isCollapsed, toast, models,
     isRecording, isProcessing, isModelReady, latestItem,
     loadSettings, saveSettings, loadHistory, clearHistory, deleteHistoryItem,
-    startRecording, stopRecording, copyToClipboard, showToast,
+    startRecording, stopRecording, copyToClipboard, showToast, listenBackendEvents,
     loadModels, loadWhisperModel, unloadWhisperModel, downloadModel, testCloudConnection,
     translationUsage, translateText, getTranslationUsage,
     pendingTranslationText,